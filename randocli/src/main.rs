@@ -1,41 +1,273 @@
 extern crate randolib;
-use std::time::Instant;
+extern crate clap;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use randolib::world::World;
+use randolib::region::Region;
+use randolib::node::Node;
+use randolib::fill::{self, FillAlgorithm, Placement};
+use randolib::doors::{self, DoorMode};
+use randolib::location::Location;
+use randolib::patch::PatchFormat;
+use randolib::rom::Rom;
+use randolib::settings::{PresetFile, Settings};
+use randolib::spoiler::Spoiler;
+use randolib::source::{DirectorySource, WorldSource};
+use std::fs;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/* Exit codes, so scripts can tell bad input apart from a seed or data problem */
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
 
 fn main() {
-    let items = 
-        vec!["Morph", "Missile", "Bombs", "Super", "PowerBomb"];
-    
-    let techs =
-        vec!["canWalljump", "canTrickyWalljump", "canMidAirMorph", "canCWJ"];
+    let data = Arg::with_name("data").long("data").takes_value(true).default_value("../sm-json-data").help("Path to the sm-json-data checkout, or a .zip or .tar archive of it");
+    let preset = Arg::with_name("preset").long("preset").takes_value(true).default_value("normal").help("Difficulty preset to use");
+    let settings = Arg::with_name("settings").long("settings").takes_value(true).help("JSON file with presets to use instead of the built-in ones");
+    let start = Arg::with_name("start").long("start").takes_value(true).default_value("ship").help("Start node name, \"ship\" for the Landing Site ship or \"random\" for a random save station");
+
+    let app = App::new("randocli")
+        .about("Super Metroid randomizer using the sm-json-data logic")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name("generate")
+            .about("Generates a seed")
+            .arg(data.clone()).arg(preset.clone()).arg(settings.clone()).arg(start.clone())
+            .arg(Arg::with_name("seed").long("seed").takes_value(true).help("Seed number, picked at random when not given"))
+            .arg(Arg::with_name("algorithm").long("algorithm").takes_value(true).possible_values(&["assumed", "forward"]).default_value("assumed"))
+            .arg(Arg::with_name("doors").long("doors").takes_value(true).possible_values(&["vanilla", "doors", "areas"]).default_value("vanilla").help("Shuffle no doors, all two-way doors, or only the doors between areas"))
+            .arg(Arg::with_name("rom").long("rom").takes_value(true).help("Vanilla ROM to patch"))
+            .arg(Arg::with_name("output").long("output").takes_value(true).requires("rom").help("Where to write the patched ROM"))
+            .arg(Arg::with_name("patch").long("patch").takes_value(true).requires("rom").help("Where to write an IPS or BPS patch, picked by the file extension"))
+            .arg(Arg::with_name("placement").long("placement").takes_value(true).help("Where to write the placement as JSON"))
+            .arg(Arg::with_name("spoiler").long("spoiler").takes_value(true).help("Where to write the spoiler log, JSON if the file ends in .json")))
+        .subcommand(SubCommand::with_name("reachable")
+            .about("Lists the item locations reachable with a set of items")
+            .arg(data.clone()).arg(preset).arg(settings.clone())
+            .arg(start.clone())
+            .arg(Arg::with_name("items").long("items").takes_value(true).use_delimiter(true).help("Comma separated item names, repeat names for multiple tanks and expansions"))
+            .arg(Arg::with_name("techs").long("techs").takes_value(true).use_delimiter(true).help("Comma separated techs, used instead of the preset techs")))
+        .subcommand(SubCommand::with_name("validate")
+            .about("Checks the world data and presets for problems")
+            .arg(data.clone()).arg(settings))
+        .subcommand(SubCommand::with_name("spoiler")
+            .about("Renders the spoiler log for a placement file, with the settings stored in it")
+            .arg(data)
+            .arg(Arg::with_name("placement").required(true).help("Placement JSON written by generate"))
+            .arg(Arg::with_name("format").long("format").takes_value(true).possible_values(&["text", "json"]).default_value("text")));
+
+    let matches = match app.get_matches_safe().and_then(check_args) {
+        Ok(matches) => matches,
+        Err(e) if e.use_stderr() => {
+            eprintln!("{}", e.message);
+            process::exit(EXIT_USAGE);
+        },
+        Err(e) => e.exit()
+    };
+
+    let result = match matches.subcommand() {
+        ("generate", Some(args)) => generate(args),
+        ("reachable", Some(args)) => reachable(args),
+        ("validate", Some(args)) => validate(args),
+        ("spoiler", Some(args)) => spoiler(args),
+        _ => unreachable!()
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        process::exit(EXIT_FAILURE);
+    }
+}
+
+/* Combinations clap can't express, checked before any data is loaded */
+fn check_args(matches: ArgMatches) -> clap::Result<ArgMatches> {
+    if let ("generate", Some(args)) = matches.subcommand() {
+        if args.value_of("doors") != Some("vanilla") && args.is_present("rom") {
+            return Err(clap::Error::with_description(
+                "Shuffled doors can't be written to the ROM yet, --doors can't be used with --rom",
+                clap::ErrorKind::ArgumentConflict
+            ));
+        }
+    }
+    Ok(matches)
+}
+
+fn load_source(args: &ArgMatches) -> Result<Box<dyn WorldSource>, Box<dyn std::error::Error>> {
+    let path = args.value_of("data").unwrap();
+    if path.ends_with(".zip") {
+        Ok(Box::new(randolib::source::from_zip(&fs::read(path)?)?))
+    } else if path.ends_with(".tar") {
+        Ok(Box::new(randolib::source::from_tar(&fs::read(path)?)?))
+    } else {
+        Ok(Box::new(DirectorySource::new(path)))
+    }
+}
+
+fn load_world(args: &ArgMatches) -> Result<World, Box<dyn std::error::Error>> {
+    Ok(World::load("Super Metroid", load_source(args)?.as_ref())?)
+}
+
+fn load_presets(args: &ArgMatches) -> Result<PresetFile, Box<dyn std::error::Error>> {
+    match args.value_of("settings") {
+        Some(path) => PresetFile::read(path),
+        None => Ok(PresetFile::default_presets())
+    }
+}
 
-    let start_location = "Morphing Ball";
+fn load_settings(args: &ArgMatches, world: &mut World) -> Result<Settings, Box<dyn std::error::Error>> {
+    let settings = load_presets(args)?.get(args.value_of("preset").unwrap())?;
+    settings.apply(world);
+    Ok(settings)
+}
 
-    println!("\nTraversing world from: {:?}\nUsing items: {:?}\nUsing techs: {:?}\n", &start_location, &items, &techs);
+fn find_start<'a>(world: &'a World, name: &str, techs: &[&str], seed: u64) -> Result<(&'a Region, &'a Node), Box<dyn std::error::Error>> {
+    if name == "ship" {
+        randolib::start::vanilla_start(world)
+    } else if name == "random" {
+        randolib::start::random_start(world, &[], techs, 1, seed)
+    } else {
+        match world.find_node(name) {
+            Some(start) => Ok(start),
+            None => Err(format!("Could not find start location {}", name).into())
+        }
+    }
+}
 
-    println!("Finding suitable locations for item placement...");
+fn generate(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let seed = match args.value_of("seed") {
+        Some(seed) => seed.parse::<u64>().map_err(|_| format!("Invalid seed {}", seed))?,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()
+    };
+    let algorithm = match args.value_of("algorithm") {
+        Some("forward") => FillAlgorithm::Forward,
+        _ => FillAlgorithm::Assumed
+    };
+    let door_mode = match args.value_of("doors") {
+        Some("doors") => DoorMode::Doors,
+        Some("areas") => DoorMode::Areas,
+        _ => DoorMode::Vanilla
+    };
 
-    let mut now = Instant::now();
-    let world = randolib::load_world("Super Metroid", "../sm-json-data");
-    if world.is_err() {
-        eprintln!("{:?}", world);
-        return;
+    let mut world = load_world(args)?;
+    let settings = load_settings(args, &mut world)?;
+    let connections = match door_mode {
+        DoorMode::Vanilla => None,
+        mode => Some(doors::randomize_doors(&world, mode, seed)?)
+    };
+    if let Some(connections) = &connections {
+        world.set_connections(connections.clone());
     }
 
-    let world = world.unwrap();
+    let techs = settings.get_techs();
+    let (start_region, start_node) = find_start(&world, args.value_of("start").unwrap(), &techs, seed)?;
+
+    let pool = world.get_item_pool();
+    let mut placement = fill::fill(&world, &pool, &techs, start_region, start_node, seed, algorithm)?;
+    placement.connections = connections;
+    placement.settings = Some(settings.clone());
+    println!("Generated seed {} starting at {}", seed, start_node.name);
 
-    println!("Loaded world in: {:?}", now.elapsed());
+    if let Some(path) = args.value_of("placement") {
+        fs::write(path, placement.to_json()?)?;
+    }
 
-    let possible_locations;
-    {
-        now = Instant::now();
+    if let Some(path) = args.value_of("spoiler") {
+        let spoiler = Spoiler::new(&world, &placement)?;
+        let text = if path.ends_with(".json") { spoiler.to_json()? } else { spoiler.to_text() };
+        fs::write(path, text)?;
+    }
+
+    if let Some(rom_path) = args.value_of("rom") {
+        let original = Rom::read(rom_path)?;
+        let mut rom = original.clone();
+        rom.apply_placement(&world, &placement)?;
+
+        if let Some(path) = args.value_of("output") {
+            rom.write(path)?;
+        }
+
+        if let Some(path) = args.value_of("patch") {
+            let format = if path.to_lowercase().ends_with(".bps") { PatchFormat::Bps } else { PatchFormat::Ips };
+            rom.write_patch(path, &original, format)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn reachable(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let mut world = load_world(args)?;
+    let settings = load_settings(args, &mut world)?;
+    let items = args.values_of("items").map(|i| i.collect::<Vec<&str>>()).unwrap_or_default();
+    let techs = match args.values_of("techs") {
+        Some(techs) => techs.collect::<Vec<&str>>(),
+        None => settings.get_techs()
+    };
+    let (start_region, start_node) = find_start(&world, args.value_of("start").unwrap(), &techs, 0)?;
+
+    let flags = world.get_flags(&items, &techs);
+    let locations = Location::available(&flags, &world, start_region, start_node).unwrap_or_default();
+
+    println!("Found {} reachable locations from {}", locations.len(), start_node.name);
+    for location in locations {
+        println!("  {} ({})", location.name, location.region.name);
+    }
+    Ok(())
+}
+
+fn validate(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    /* Report every broken file at once instead of just the first one */
+    let world = match World::load_collecting("Super Metroid", load_source(args)?.as_ref()) {
+        Ok(world) => world,
+        Err(errors) => {
+            for error in &errors {
+                println!("{}", error);
+            }
+            return Err(format!("Could not load the world data, {} files have errors", errors.len()).into());
+        }
+    };
+    let presets = load_presets(args)?;
+    let mut problems = Vec::new();
+
+    let locations = world.get_item_locations().map(|l| l.len()).unwrap_or(0);
+    let pool = world.get_item_pool().len();
+    if locations != pool {
+        problems.push(format!("{} item locations but only {} have a vanilla item", locations, pool));
+    }
+
+    problems.extend(randolib::validate::validate(&world).iter().map(|d| d.to_string()));
+
+    for name in presets.names() {
+        let settings = presets.get(name)?;
+        for tech in settings.unknown_techs(&world) {
+            problems.push(format!("Preset {} uses unknown tech {}", name, tech));
+        }
+        for (room, strat) in settings.unknown_strats(&world) {
+            problems.push(format!("Preset {} toggles unknown strat {} in {}", name, strat, room));
+        }
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+
+    if problems.is_empty() {
+        println!("No problems found");
+        Ok(())
+    } else {
+        Err(format!("Found {} problems", problems.len()).into())
+    }
+}
 
-        let start_node = world.regions.iter().flat_map(|r| &r.nodes).find(|n| n.name == start_location).unwrap();
-        let start_region = world.regions.iter().find(|r| r.nodes.contains(&start_node)).unwrap();
-        
-        let game_flags = world.get_flags(&items, &techs);
-        possible_locations = randolib::location::Location::available(&game_flags, &world, start_region, start_node).unwrap();
+fn spoiler(args: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let mut world = load_world(args)?;
+    let placement = Placement::from_json(&fs::read_to_string(args.value_of("placement").unwrap())?)?;
+    placement.apply(&mut world);
+    let spoiler = Spoiler::new(&world, &placement)?;
 
-        println!("Traversed world graph in: {:?}", now.elapsed());
-        println!("Found {:?} possible locations:\n{:?}\n", &possible_locations.len(), &possible_locations.iter().map(|p| p.name.as_ref()).collect::<Vec<&str>>());
+    match args.value_of("format") {
+        Some("json") => println!("{}", spoiler.to_json()?),
+        _ => print!("{}", spoiler.to_text())
     }
+    Ok(())
 }
@@ -0,0 +1,66 @@
+#ifndef RANDOLIB_H
+#define RANDOLIB_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct World World;
+
+// The message for the last failed call on this thread, or null. Free it with rando_string_free.
+char *rando_last_error(void);
+
+// Loads a world from an sm-json-data checkout. Free it with rando_world_free.
+//
+// # Safety
+// `name` and `path` must be valid null terminated strings.
+struct World *rando_world_load(const char *name, const char *path);
+
+// # Safety
+// `world` must come from rando_world_load and not be used after this call.
+void rando_world_free(struct World *world);
+
+// JSON array with the names of the item locations reachable from the start node.
+//
+// # Safety
+// `world` must come from rando_world_load, the other arguments must be valid null terminated strings or null for an empty list.
+char *rando_reachable(const struct World *world,
+                      const char *items,
+                      const char *techs,
+                      const char *start);
+
+// Fills the vanilla item pool and returns the placement as JSON. Algorithm 0 is assumed fill and 1 is forward fill.
+//
+// # Safety
+// `world` must come from rando_world_load, `techs` and `start` must be valid null terminated strings.
+char *rando_generate(const struct World *world,
+                     const char *techs,
+                     const char *start,
+                     uint64_t seed,
+                     uint32_t algorithm);
+
+// Applies a placement to a ROM and returns a patch against it. Format 0 is IPS and 1 is BPS.
+// The length of the patch is written to `out_length`.
+//
+// # Safety
+// `world` must come from rando_world_load, `placement` must be a null terminated string,
+// `rom` must point to `rom_length` readable bytes and `out_length` must be writable.
+uint8_t *rando_create_patch(const struct World *world,
+                            const char *placement,
+                            const uint8_t *rom,
+                            uintptr_t rom_length,
+                            uint32_t format,
+                            uintptr_t *out_length);
+
+// # Safety
+// `s` must be a string returned by this library or null.
+void rando_string_free(char *s);
+
+// # Safety
+// `buffer` and `length` must be a buffer and its length as returned by this library, or null.
+void rando_buffer_free(uint8_t *buffer, uintptr_t length);
+
+#endif /* RANDOLIB_H */
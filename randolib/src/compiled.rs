@@ -0,0 +1,572 @@
+use crate::world::World;
+use crate::region::Region;
+use crate::link::Strat;
+use crate::requirement::Requirement;
+use crate::enemy::Enemy;
+use crate::weapon::Weapon;
+use crate::location::State;
+use crate::inventory::Inventory;
+use crate::resources::{AmmoType, Resources, RESOURCE_ITEMS};
+use crate::damage::{Environment, Suit};
+use std::collections::HashMap;
+
+/* Set of interned names, one bit per id */
+#[derive(Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Flags {
+    bits: Vec<u64>
+}
+
+impl Flags {
+    pub fn new() -> Flags {
+        Flags::default()
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.bits.get(id / 64).is_some_and(|b| b & (1 << (id % 64)) != 0)
+    }
+
+    /* Returns true if the id wasn't in the set yet */
+    pub fn insert(&mut self, id: usize) -> bool {
+        if self.bits.len() <= id / 64 {
+            self.bits.resize(id / 64 + 1, 0);
+        }
+        let added = !self.contains(id);
+        self.bits[id / 64] |= 1 << (id % 64);
+        added
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&b| b == 0)
+    }
+
+    pub fn union(&mut self, other: &Flags) {
+        if self.bits.len() < other.bits.len() {
+            self.bits.resize(other.bits.len(), 0);
+        }
+        for (bits, other) in self.bits.iter_mut().zip(&other.bits) {
+            *bits |= other;
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.iter().enumerate().flat_map(|(i, &b)| (0..64).filter(move |bit| b & (1 << bit) != 0).map(move |bit| i * 64 + bit))
+    }
+}
+
+/* Ids for every item in the world and every helper, tech and event name the data refers to */
+#[derive(Default, Debug)]
+pub struct Names {
+    ids: HashMap<String, usize>,
+    names: Vec<String>
+}
+
+impl Names {
+    pub fn intern(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.ids.insert(name.to_string(), id);
+        self.names.push(name.to_string());
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/* A Requirement with names turned into ids and enemies, weapons and attacks looked up ahead of time.
+   Requirements that can never be met, like damage from an attack missing from the enemy data, become Never. */
+#[derive(Clone, PartialEq, Debug)]
+pub enum CompiledRequirement {
+    Always,
+    Never,
+    Or(Vec<CompiledRequirement>),
+    And(Vec<CompiledRequirement>),
+    Not(Vec<CompiledRequirement>),
+    Flag(usize),
+    AdjacentRunway { from_node: i64, used_tiles: i64 },
+    CanShineCharge { used_tiles: i64 },
+    CanComeInCharged { from_node: i64, frames_remaining: i64 },
+    EnemyDamage { base_damage: i64, hits: i64 },
+    Environment { environment: Environment, amount: i64 },
+    Ammo { ammo_type: AmmoType, count: i64 },
+    AmmoDrain { ammo_type: AmmoType, count: i64 },
+    EnemyKill { weapons: Vec<usize>, groups: Vec<KillGroup> },
+    EnergyAtMost(i64)
+}
+
+/* Shots needed to kill each enemy in the group with each of the candidate weapons of the enemyKill */
+#[derive(Clone, PartialEq, Debug)]
+pub struct KillGroup {
+    pub shots: Vec<Vec<Option<i64>>>
+}
+
+#[derive(Debug)]
+pub struct CompiledStrat {
+    pub name: String,
+    pub notable: bool,
+    pub requires: Option<CompiledRequirement>,
+    pub obstacles: Option<Vec<CompiledObstacle>>
+}
+
+/* `index` points into CompiledRegion::obstacles */
+#[derive(Debug)]
+pub struct CompiledObstacle {
+    pub index: usize,
+    pub requires: Option<CompiledRequirement>,
+    pub bypass: Option<CompiledRequirement>
+}
+
+#[derive(Debug)]
+pub struct CompiledLock {
+    pub lock: Option<CompiledRequirement>,
+    pub unlock_strats: Option<Vec<CompiledStrat>>,
+    pub bypass_strats: Option<Vec<CompiledStrat>>
+}
+
+#[derive(Debug)]
+pub struct CompiledRunway {
+    pub length: i64,
+    pub strats: Option<Vec<CompiledStrat>>
+}
+
+#[derive(Debug)]
+pub struct CompiledLeaveCharged {
+    pub used_tiles: i64,
+    pub frames_remaining: i64,
+    pub strats: Option<Vec<CompiledStrat>>
+}
+
+#[derive(Debug)]
+pub struct CompiledNode {
+    pub interaction: Option<CompiledRequirement>,
+    pub locks: Vec<CompiledLock>,
+    pub yields: Vec<usize>,
+    pub runways: Vec<CompiledRunway>,
+    pub leave_charged: Vec<CompiledLeaveCharged>
+}
+
+/* Laid out like the region, so nodes[i] is region.nodes[i] and links[l][t] holds the strats of region.links[l].to[t] */
+#[derive(Debug)]
+pub struct CompiledRegion {
+    pub nodes: Vec<CompiledNode>,
+    pub links: Vec<Vec<Option<Vec<CompiledStrat>>>>,
+    pub obstacles: Vec<String>
+}
+
+#[derive(Debug)]
+pub struct CompiledWeapon {
+    pub use_requires: CompiledRequirement,
+    pub shot_requires: Option<CompiledRequirement>
+}
+
+/* Every requirement in the world in a form that is cheap to check over and over during a fill.
+   Built together with the graph, helpers and techs are only referenced by id and resolved up front by World::get_flags. */
+#[derive(Default, Debug)]
+pub struct Compiled {
+    pub names: Names,
+    pub regions: Vec<CompiledRegion>,
+    pub weapons: Vec<CompiledWeapon>,
+    pub helpers: Vec<(usize, Option<CompiledRequirement>)>,
+    pub techs: Vec<(usize, Option<CompiledRequirement>)>,
+    pub speed_booster: usize,
+    pub varia: usize,
+    pub gravity: usize
+}
+
+impl Compiled {
+    pub fn build(world: &World) -> Compiled {
+        let mut names = Names::default();
+        for item in RESOURCE_ITEMS.iter().copied().chain(world.get_item_pool()) {
+            names.intern(item);
+        }
+        let speed_booster = names.intern("SpeedBooster");
+        let varia = names.intern("Varia");
+        let gravity = names.intern("Gravity");
+
+        let mut compiler = Compiler { names, enemies: &world.enemies, weapons: &world.weapons };
+        let helpers = world.helpers.iter().map(|h| (compiler.names.intern(&h.name), h.requires.as_ref().map(|r| compiler.requirement(r)))).collect();
+        let techs = world.techs.iter().map(|t| (compiler.names.intern(&t.name), t.requires.as_ref().map(|r| compiler.requirement(r)))).collect();
+        let weapons = world.weapons.iter().map(|w| CompiledWeapon {
+            use_requires: compiler.requirement(&w.useRequires),
+            shot_requires: w.shotRequires.as_ref().map(|r| compiler.requirement(r))
+        }).collect();
+        let regions = world.regions.iter().map(|r| compiler.region(r)).collect();
+
+        Compiled { names: compiler.names, regions, weapons, helpers, techs, speed_booster, varia, gravity }
+    }
+
+    pub fn suit(&self, items: &Inventory) -> Suit {
+        if items.contains(self.gravity) {
+            Suit::Gravity
+        } else if items.contains(self.varia) {
+            Suit::Varia
+        } else {
+            Suit::Power
+        }
+    }
+}
+
+struct Compiler<'a> {
+    names: Names,
+    enemies: &'a [Enemy],
+    weapons: &'a [Weapon]
+}
+
+impl Compiler<'_> {
+    fn region(&mut self, region: &Region) -> CompiledRegion {
+        let mut obstacles = region.obstacles.iter().flatten().map(|o| o.id.clone().unwrap_or_default()).collect::<Vec<String>>();
+
+        let nodes = region.nodes.iter().map(|node| CompiledNode {
+            interaction: node.interactionRequires.as_ref().map(|r| self.requirement(r)),
+            locks: node.locks.iter().flatten().map(|lock| CompiledLock {
+                lock: lock.lock.as_ref().map(|r| self.requirement(r)),
+                unlock_strats: self.strats(&lock.unlockStrats, &mut obstacles),
+                bypass_strats: self.strats(&lock.bypassStrats, &mut obstacles)
+            }).collect(),
+            yields: node.yields.iter().flatten().map(|y| self.names.intern(y)).collect(),
+            runways: node.get_runways().map(|r| CompiledRunway { length: r.length, strats: self.strats(&r.strats, &mut obstacles) }).collect(),
+            leave_charged: node.sparking.iter().flat_map(|s| s.canLeaveCharged.iter().flatten()).map(|c| CompiledLeaveCharged {
+                used_tiles: c.usedTiles,
+                frames_remaining: c.framesRemaining,
+                strats: self.strats(&c.strats, &mut obstacles)
+            }).collect()
+        }).collect();
+
+        let links = region.links.iter().map(|l| l.to.iter().map(|t| self.strats(&t.strats, &mut obstacles)).collect()).collect();
+
+        CompiledRegion { nodes, links, obstacles }
+    }
+
+    /* Obstacles a strat names that the room doesn't list still get an index of their own, like they did when they were keyed by name */
+    fn strats(&mut self, strats: &Option<Vec<Strat>>, obstacles: &mut Vec<String>) -> Option<Vec<CompiledStrat>> {
+        strats.as_ref().map(|strats| strats.iter().map(|s| CompiledStrat {
+            name: s.name.to_string(),
+            notable: s.notable,
+            requires: s.requires.as_ref().map(|r| self.requirement(r)),
+            obstacles: s.obstacles.as_ref().map(|o| o.iter().map(|o| {
+                let id = o.id.clone().unwrap_or_default();
+                let index = match obstacles.iter().position(|name| name == &id) {
+                    Some(index) => index,
+                    None => {
+                        obstacles.push(id);
+                        obstacles.len() - 1
+                    }
+                };
+                CompiledObstacle {
+                    index,
+                    requires: o.requires.as_ref().map(|r| self.requirement(r)),
+                    bypass: o.bypass.as_ref().map(|r| self.requirement(r))
+                }
+            }).collect())
+        }).collect())
+    }
+
+    fn requirements(&mut self, reqs: &[Requirement]) -> Vec<CompiledRequirement> {
+        reqs.iter().map(|r| self.requirement(r)).collect()
+    }
+
+    fn requirement(&mut self, requirement: &Requirement) -> CompiledRequirement {
+        match requirement {
+            Requirement::Or { or: reqs } => CompiledRequirement::Or(self.requirements(reqs)),
+            Requirement::ExplicitAnd { and: reqs } | Requirement::And(reqs) => CompiledRequirement::And(self.requirements(reqs)),
+            Requirement::Not { not: reqs } => CompiledRequirement::Not(self.requirements(reqs)),
+            Requirement::Req(name) => CompiledRequirement::Flag(self.names.intern(name)),
+            Requirement::AdjacentRunway { adjacentRunway: a } => CompiledRequirement::AdjacentRunway { from_node: a.fromNode, used_tiles: a.usedTiles },
+            Requirement::CanShineCharge { canShineCharge: c } => CompiledRequirement::CanShineCharge { used_tiles: c.usedTiles },
+            Requirement::CanComeInCharged { canComeInCharged: c } => CompiledRequirement::CanComeInCharged { from_node: c.fromNode, frames_remaining: c.framesRemaining },
+            Requirement::EnemyDamage { enemyDamage: ed } => {
+                match self.enemies.iter().find(|e| e.name == ed.enemy).and_then(|e| e.get_attack(&ed.r#type)) {
+                    Some(attack) => CompiledRequirement::EnemyDamage { base_damage: attack.baseDamage, hits: ed.hits },
+                    None => CompiledRequirement::Never
+                }
+            },
+            Requirement::Ammo { ammo: a } => match AmmoType::from_name(&a.r#type) {
+                Some(ammo_type) => CompiledRequirement::Ammo { ammo_type, count: a.count },
+                None => CompiledRequirement::Never
+            },
+            Requirement::AmmoDrain { ammoDrain: a } => match AmmoType::from_name(&a.r#type) {
+                Some(ammo_type) => CompiledRequirement::AmmoDrain { ammo_type, count: a.count },
+                None => CompiledRequirement::Never
+            },
+            Requirement::SpikeHits { spikeHits: s } => CompiledRequirement::Environment { environment: Environment::Spikes, amount: *s },
+            Requirement::HeatFrames { heatFrames: h } => CompiledRequirement::Environment { environment: Environment::Heat, amount: *h },
+            Requirement::AcidFrames { acidFrames: a } => CompiledRequirement::Environment { environment: Environment::Acid, amount: *a },
+            Requirement::LavaFrames { lavaFrames: l } => CompiledRequirement::Environment { environment: Environment::Lava, amount: *l },
+            Requirement::DraygonElectricityFrames { draygonElectricityFrames: d } => CompiledRequirement::Environment { environment: Environment::Electricity, amount: *d },
+            Requirement::HibashiHits { hibashiHits: h } => CompiledRequirement::Environment { environment: Environment::Hibashi, amount: *h },
+            Requirement::EnergyAtMost { energyAtMost: e } => CompiledRequirement::EnergyAtMost(*e),
+            Requirement::EnemyKill { enemyKill: e } => {
+                /* Weapons can be named directly or by one of their categories, like "Beam" */
+                let named = |w: &Weapon, names: &Vec<String>| names.iter().any(|n| &w.name == n || w.categories.contains(n));
+                let weapons = self.weapons.iter().enumerate()
+                    .filter(|(_, w)| !w.situational || e.explicitWeapons.is_some())
+                    .filter(|(_, w)| e.explicitWeapons.as_ref().is_none_or(|ew| named(w, ew)))
+                    .filter(|(_, w)| !e.excludedWeapons.as_ref().is_some_and(|ew| named(w, ew)))
+                    .map(|(i, _)| i)
+                    .collect::<Vec<usize>>();
+
+                /* An enemy missing from the data can't be killed, so neither can its group */
+                let groups = e.enemies.iter().map(|group| Some(KillGroup {
+                    shots: group.iter()
+                        .map(|name| self.enemies.iter().find(|we| &we.name == name))
+                        .map(|enemy| enemy.map(|enemy| weapons.iter().map(|&w| enemy.shots_to_kill(&self.weapons[w])).collect()))
+                        .collect::<Option<_>>()?
+                })).collect::<Option<Vec<KillGroup>>>();
+
+                match groups {
+                    Some(groups) => CompiledRequirement::EnemyKill { weapons, groups },
+                    None => CompiledRequirement::Never
+                }
+            },
+            Requirement::CanVisitNode { .. } | Requirement::ResetRoom { .. } | Requirement::PreviousNode { .. } |
+            Requirement::PreviousStratProperty { .. } | Requirement::None => CompiledRequirement::Always
+        }
+    }
+}
+
+impl CompiledRequirement {
+    /* Checks the requirement against our items and pays for it out of state.resources.
+       Resources are left untouched when the check fails. */
+    pub fn check(&self, items: &Inventory, world: &World, state: &mut State) -> bool {
+        let resources = state.resources;
+        let result = self.check_and_pay(items, world, state);
+        if !result {
+            state.resources = resources;
+        }
+        result
+    }
+
+    fn check_and_pay(&self, items: &Inventory, world: &World, state: &mut State) -> bool {
+        let compiled = &world.compiled;
+        match self {
+            CompiledRequirement::Always => true,
+            CompiledRequirement::Never => false,
+            CompiledRequirement::Or(reqs) => CompiledRequirement::any(reqs, items, world, state),
+            CompiledRequirement::And(reqs) => reqs.iter().all(|r| r.check(items, world, state)),
+            CompiledRequirement::Not(reqs) => {
+                let resources = state.resources;
+                let result = !reqs.iter().any(|r| r.check(items, world, state));
+                state.resources = resources;
+                result
+            },
+            CompiledRequirement::Flag(id) => items.contains(*id) || state.events.contains(*id),
+            CompiledRequirement::AdjacentRunway { from_node, used_tiles } => state.entry.is_some_and(|e| e.node == *from_node && e.runway >= *used_tiles),
+            CompiledRequirement::CanShineCharge { used_tiles } => items.contains(compiled.speed_booster) && *used_tiles >= world.logic.shine_charge_tiles,
+            CompiledRequirement::CanComeInCharged { from_node, frames_remaining } => {
+                items.contains(compiled.speed_booster) && state.entry.is_some_and(|e| e.node == *from_node && e.frames_remaining.is_some_and(|f| f >= *frames_remaining))
+            },
+            CompiledRequirement::EnemyDamage { base_damage, hits } => state.resources.spend_energy(world.logic.enemy_damage(*base_damage, *hits, compiled.suit(items))),
+            CompiledRequirement::Environment { environment, amount } => state.resources.spend_energy(world.logic.environment_damage(*environment, *amount, compiled.suit(items))),
+            CompiledRequirement::Ammo { ammo_type, count } => state.resources.spend_ammo(*ammo_type, *count),
+            CompiledRequirement::AmmoDrain { ammo_type, count } => {
+                state.resources.drain_ammo(*ammo_type, *count);
+                true
+            },
+            CompiledRequirement::EnergyAtMost(energy) => {
+                state.resources.energy = state.resources.energy.min(*energy);
+                true
+            },
+            CompiledRequirement::EnemyKill { weapons, groups } => {
+                let usable = weapons.iter().map(|&w| CompiledRequirement::can_use(w, items, world, state)).collect::<Vec<bool>>();
+                groups.iter().all(|group| CompiledRequirement::kill_group(group, weapons, &usable, items, world, state))
+            }
+        }
+    }
+
+    /* Same as State::cheapest, but requirements only ever spend resources, so a branch that costs nothing
+       can't be beaten and the rest don't have to be tried */
+    fn any(reqs: &[CompiledRequirement], items: &Inventory, world: &World, state: &mut State) -> bool {
+        let before = state.resources;
+        let mut best: Option<Resources> = None;
+        for r in reqs {
+            if r.check(items, world, state) {
+                if state.resources == before {
+                    return true;
+                }
+                if best.is_none_or(|b| state.resources.total() > b.total()) {
+                    best = Some(state.resources);
+                }
+                state.resources = before;
+            }
+        }
+
+        match best {
+            Some(resources) => {
+                state.resources = resources;
+                true
+            },
+            None => false
+        }
+    }
+
+    fn can_use(weapon: usize, items: &Inventory, world: &World, state: &mut State) -> bool {
+        let resources = state.resources;
+        let usable = world.compiled.weapons[weapon].use_requires.check(items, world, state);
+        state.resources = resources;
+        usable
+    }
+
+    fn fire(weapon: usize, shots: i64, items: &Inventory, world: &World, state: &mut State) -> bool {
+        match &world.compiled.weapons[weapon].shot_requires {
+            Some(r) => (0..shots).all(|_| r.check(items, world, state)),
+            None => true
+        }
+    }
+
+    /* Kills a group of enemies as cheaply as we can, either with one weapon that hits the whole group at once
+       or by picking the cheapest weapon for each enemy on its own */
+    fn kill_group(group: &KillGroup, weapons: &[usize], usable: &[bool], items: &Inventory, world: &World, state: &mut State) -> bool {
+        let candidates = (0..weapons.len()).filter(|&w| usable[w]).collect::<Vec<usize>>();
+        let mut options: Vec<Option<usize>> = candidates.iter().filter(|&&w| world.weapons[weapons[w]].hitsGroup && group.shots.len() > 1).map(|&w| Some(w)).collect();
+        options.push(None);
+
+        state.cheapest(&options, |option, state| match option {
+            Some(w) => match group.shots.iter().map(|shots| shots[*w]).collect::<Option<Vec<i64>>>() {
+                Some(shots) => CompiledRequirement::fire(weapons[*w], shots.into_iter().max().unwrap_or(0), items, world, state),
+                None => false
+            },
+            None => group.shots.iter().all(|shots| state.cheapest(&candidates, |&w, state| match shots[w] {
+                Some(count) => CompiledRequirement::fire(weapons[w], count, items, world, state),
+                None => false
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompiledRequirement, Flags};
+    use crate::world::World;
+    use crate::location::State;
+    use crate::resources::Resources;
+    use crate::test_world;
+
+    /* A world with a single helper, so the requirement gets compiled the same way as the ones in the data */
+    fn compile(requires: &str) -> World {
+        let helpers = format!(r#"{{"$schema": "", "helpers": [{{"name": "h_test", "requires": {}}}]}}"#, requires);
+        test_world::world_with(&[("helpers.json", &helpers)])
+    }
+
+    fn requirement(world: &World) -> &CompiledRequirement {
+        world.compiled.helpers[0].1.as_ref().unwrap()
+    }
+
+    /* Checks the requirement with full ammo for the items and returns what is left afterwards */
+    fn check(world: &World, items: &[&str]) -> Option<Resources> {
+        let items = world.inventory(items);
+        let mut state = State::with_capacity(0, 0, Resources::from_inventory(&items, &world.compiled.names));
+        if requirement(world).check(&items, world, &mut state) {
+            Some(state.resources)
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn boss_needs_two_missile_packs() {
+        let world = compile(r#"[{"enemyKill": {"enemies": [["Boss"]]}}]"#);
+        assert_eq!(check(&world, &["Missile"]), None);
+        assert_eq!(check(&world, &["Missile", "Missile"]).map(|r| r.missiles), Some(2));
+    }
+
+    #[test]
+    fn excluded_weapon_forces_another() {
+        let world = compile(r#"[{"enemyKill": {"enemies": [["Boss"]], "excludedWeapons": ["Missile"]}}]"#);
+        assert_eq!(check(&world, &["Missile", "Missile"]), None);
+
+        let left = check(&world, &["Missile", "Missile", "Super"]).unwrap();
+        assert_eq!((left.missiles, left.supers), (10, 3));
+    }
+
+    #[test]
+    fn weapons_are_matched_by_category() {
+        let world = compile(r#"[{"enemyKill": {"enemies": [["Zoomer"]]}}]"#);
+        assert_eq!(check(&world, &[]), Some(Resources::base()));
+
+        let world = compile(r#"[{"enemyKill": {"enemies": [["Zoomer"]], "excludedWeapons": ["Beam"]}}]"#);
+        assert_eq!(check(&world, &[]), None);
+        assert_eq!(check(&world, &["Missile"]).map(|r| r.missiles), Some(4));
+    }
+
+    #[test]
+    fn flags_across_word_boundaries() {
+        let mut flags = Flags::new();
+        assert!(flags.is_empty());
+        for &id in &[63, 64, 127, 128] {
+            assert!(flags.insert(id));
+        }
+        assert!(!flags.insert(64));
+
+        assert!(flags.contains(63) && flags.contains(64) && flags.contains(127) && flags.contains(128));
+        assert!(!flags.contains(62) && !flags.contains(65) && !flags.contains(129) && !flags.contains(1000));
+        assert_eq!(flags.len(), 4);
+        assert_eq!(flags.iter().collect::<Vec<usize>>(), vec![63, 64, 127, 128]);
+    }
+
+    #[test]
+    fn flags_union() {
+        let mut flags = Flags::new();
+        flags.insert(1);
+        let mut other = Flags::new();
+        other.insert(1);
+        other.insert(130);
+
+        flags.union(&other);
+        assert_eq!(flags.iter().collect::<Vec<usize>>(), vec![1, 130]);
+
+        /* A shorter set doesn't drop the high words */
+        flags.union(&Flags::new());
+        assert_eq!(flags.len(), 2);
+    }
+
+    #[test]
+    fn or_stops_at_a_free_branch() {
+        for requires in &[
+            r#"[{"or": ["Morph", {"ammo": {"type": "Missile", "count": 1}}]}]"#,
+            r#"[{"or": [{"ammo": {"type": "Missile", "count": 1}}, "Morph"]}]"#
+        ] {
+            let world = compile(requires);
+            assert_eq!(check(&world, &["Morph", "Missile"]).map(|r| r.missiles), Some(5));
+            assert_eq!(check(&world, &["Missile"]).map(|r| r.missiles), Some(4));
+        }
+    }
+
+    #[test]
+    fn or_picks_the_cheapest_branch() {
+        let world = compile(r#"[{"or": [{"ammo": {"type": "Missile", "count": 3}}, {"ammo": {"type": "Missile", "count": 1}}]}]"#);
+        assert_eq!(check(&world, &["Missile"]).map(|r| r.missiles), Some(4));
+    }
+
+    #[test]
+    fn missing_data_compiles_to_never() {
+        for requires in &[
+            r#"[{"enemyDamage": {"enemy": "Zoomer", "type": "laser", "hits": 1}}]"#,
+            r#"[{"enemyDamage": {"enemy": "Ridley", "type": "contact", "hits": 1}}]"#,
+            r#"[{"ammo": {"type": "Bullets", "count": 1}}]"#,
+            r#"[{"ammoDrain": {"type": "Bullets", "count": 1}}]"#,
+            r#"[{"enemyKill": {"enemies": [["Zoomer", "Ridley"]]}}]"#
+        ] {
+            let world = compile(requires);
+            assert_eq!(requirement(&world), &CompiledRequirement::And(vec![CompiledRequirement::Never]), "{}", requires);
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use serde_derive::{Serialize, Deserialize};
+use crate::load_error::{LoadError, parse_json};
 use std::fs;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Connection {
     pub connectionType: String,
     pub description: Option<String>,
@@ -17,18 +18,22 @@ pub struct ConnectionFile {
 }
 
 impl ConnectionFile {
-    pub fn read(path: &str) -> Result<Vec<Connection>, Box<dyn std::error::Error>> {
-        let data = fs::read_to_string(path)?;
-        let connection_file: ConnectionFile = serde_json::from_str(&data)?;
+    pub fn read(path: &str) -> Result<Vec<Connection>, LoadError> {
+        let data = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+        ConnectionFile::parse(&data, path)
+    }
+
+    pub fn parse(data: &str, path: &str) -> Result<Vec<Connection>, LoadError> {
+        let connection_file: ConnectionFile = parse_json(data, path)?;
         if let Some(connections) = connection_file.connections {
             Ok(connections)
         } else {
-            bail!(format!("Could not find connections node in connection file: {:?}", path))
+            Err(LoadError::missing_section(path, "connections"))
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct ConnectionNode {
     pub area: String,
     pub subarea: String,
@@ -0,0 +1,120 @@
+use serde_derive::{Serialize, Deserialize};
+
+/* The best suit we have, Gravity Suit takes over from Varia Suit */
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Suit {
+    Power,
+    Varia,
+    Gravity
+}
+
+/* Damage taken from an enemy attack after suit reductions, Gravity Suit takes a quarter and Varia Suit half */
+pub fn suit_damage(base_damage: i64, suit: Suit) -> i64 {
+    match suit {
+        Suit::Gravity => base_damage / 4,
+        Suit::Varia => base_damage / 2,
+        Suit::Power => base_damage
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Environment {
+    Heat,
+    Lava,
+    Acid,
+    Electricity,
+    Spikes,
+    Hibashi
+}
+
+/* Energy lost per frame (or per hit for spikes and hibashi), and the multipliers applied while wearing a suit */
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct EnvironmentDamage {
+    pub damage: f64,
+    pub varia: f64,
+    pub gravity: f64
+}
+
+impl EnvironmentDamage {
+    pub fn new(damage: f64, varia: f64, gravity: f64) -> EnvironmentDamage {
+        EnvironmentDamage { damage, varia, gravity }
+    }
+
+    pub fn get_damage(&self, amount: i64, suit: Suit) -> f64 {
+        let multiplier = match suit {
+            Suit::Gravity => self.gravity,
+            Suit::Varia => self.varia,
+            Suit::Power => 1.0
+        };
+        amount as f64 * self.damage * multiplier
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct DamageTable {
+    pub heat: EnvironmentDamage,
+    pub lava: EnvironmentDamage,
+    pub acid: EnvironmentDamage,
+    pub electricity: EnvironmentDamage,
+    pub spikes: EnvironmentDamage,
+    pub hibashi: EnvironmentDamage
+}
+
+impl Default for DamageTable {
+    fn default() -> DamageTable {
+        DamageTable {
+            heat: EnvironmentDamage::new(0.25, 0.0, 0.0),
+            lava: EnvironmentDamage::new(0.5, 1.0, 0.0),
+            acid: EnvironmentDamage::new(1.5, 0.5, 0.25),
+            electricity: EnvironmentDamage::new(1.0, 1.0, 1.0),
+            spikes: EnvironmentDamage::new(60.0, 0.5, 0.25),
+            hibashi: EnvironmentDamage::new(30.0, 0.5, 0.25)
+        }
+    }
+}
+
+impl DamageTable {
+    pub fn get(&self, environment: Environment) -> &EnvironmentDamage {
+        match environment {
+            Environment::Heat => &self.heat,
+            Environment::Lava => &self.lava,
+            Environment::Acid => &self.acid,
+            Environment::Electricity => &self.electricity,
+            Environment::Spikes => &self.spikes,
+            Environment::Hibashi => &self.hibashi
+        }
+    }
+}
+
+/* Multipliers on frame counts and hits from the data, so players can ask for more room for error */
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct Leniency {
+    pub heat: f64,
+    pub lava: f64,
+    pub acid: f64,
+    pub electricity: f64,
+    pub spikes: f64,
+    pub hibashi: f64,
+    pub enemy_damage: f64
+}
+
+impl Default for Leniency {
+    fn default() -> Leniency {
+        Leniency { heat: 1.0, lava: 1.0, acid: 1.0, electricity: 1.0, spikes: 1.0, hibashi: 1.0, enemy_damage: 1.0 }
+    }
+}
+
+impl Leniency {
+    pub fn get(&self, environment: Environment) -> f64 {
+        match environment {
+            Environment::Heat => self.heat,
+            Environment::Lava => self.lava,
+            Environment::Acid => self.acid,
+            Environment::Electricity => self.electricity,
+            Environment::Spikes => self.spikes,
+            Environment::Hibashi => self.hibashi
+        }
+    }
+}
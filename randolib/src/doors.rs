@@ -0,0 +1,243 @@
+use serde_derive::{Serialize, Deserialize};
+use crate::world::World;
+use crate::connection::{Connection, ConnectionNode};
+use crate::node::NodeSubType;
+use crate::rng::Rng;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const MAX_ATTEMPTS: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum DoorMode {
+    Vanilla,
+    /* Every two-way door can lead anywhere */
+    Doors,
+    /* Only doors between areas are shuffled, the rooms inside an area stay connected like in vanilla */
+    Areas
+}
+
+/* One side of a shuffled connection. Doors only fit together if they have the same connection type,
+   are both elevators or both regular doors, and face each other (left to right or top to bottom). */
+struct DoorEnd {
+    node: ConnectionNode,
+    connection_type: String,
+    elevator: bool,
+    vertical: bool,
+    first: bool,
+    component: usize
+}
+
+impl DoorEnd {
+    fn fits(&self, other: &DoorEnd) -> bool {
+        self.connection_type == other.connection_type && self.elevator == other.elevator && self.vertical == other.vertical && self.first != other.first
+    }
+}
+
+/* Builds a new set of connections for the world. One-way connections never move, and the rooms
+   joined by the shuffled doors are linked up one by one from a single starting point so every room
+   that had a door connection in vanilla can still be reached from every other. */
+pub fn randomize_doors(world: &World, mode: DoorMode, seed: u64) -> Result<Vec<Connection>, Box<dyn std::error::Error>> {
+    let shuffled = |c: &Connection| match mode {
+        DoorMode::Vanilla => false,
+        DoorMode::Doors => c.connectionType == "Bidirectional",
+        DoorMode::Areas => c.connectionType == "Bidirectional" && c.nodes.len() == 2 && c.nodes[0].area != c.nodes[1].area
+    };
+    let (moving, fixed): (Vec<&Connection>, Vec<&Connection>) = world.connections.iter().partition(|c| shuffled(c));
+
+    /* Rooms held together by the connections that stay put act as one piece */
+    let mut components = Components::default();
+    for connection in &fixed {
+        if let [a, b] = &connection.nodes[..] {
+            components.join(a.roomid, b.roomid);
+        }
+    }
+
+    let mut ends = Vec::new();
+    for connection in &moving {
+        if connection.nodes.len() != 2 {
+            bail!(format!("Connection {:?} does not join two doors", connection.description))
+        }
+        for node in &connection.nodes {
+            let elevator = world.get_node(node.roomid, node.nodeid).is_some_and(|(_, n)| n.nodeSubType == Some(NodeSubType::Elevator));
+            let (vertical, first) = match node.position.as_ref() {
+                "left" => (false, true),
+                "right" => (false, false),
+                "top" => (true, true),
+                "bottom" => (true, false),
+                position => bail!(format!("Unknown door position {} for node {}:{}", position, node.roomid, node.nodeid))
+            };
+            let component = components.find(node.roomid);
+            ends.push(DoorEnd { node: node.clone(), connection_type: connection.connectionType.to_string(), elevator, vertical, first, component });
+        }
+    }
+
+    let mut rng = Rng::new(seed);
+    let mut pairs = None;
+    for _ in 0..MAX_ATTEMPTS {
+        pairs = pair_doors(&ends, &mut rng);
+        if pairs.is_some() {
+            break;
+        }
+    }
+
+    let pairs = match pairs {
+        Some(pairs) => pairs,
+        None => bail!(format!("Could not connect all rooms after {} attempts", MAX_ATTEMPTS))
+    };
+
+    let mut connections = fixed.into_iter().cloned().collect::<Vec<Connection>>();
+    for (a, b) in pairs {
+        let (a, b) = if ends[a].first { (&ends[a], &ends[b]) } else { (&ends[b], &ends[a]) };
+        connections.push(Connection {
+            connectionType: a.connection_type.to_string(),
+            description: Some(format!("{}:{} to {}:{}", a.node.roomid, a.node.nodeid, b.node.roomid, b.node.nodeid)),
+            nodes: vec![a.node.clone(), b.node.clone()]
+        });
+    }
+    Ok(connections)
+}
+
+/* Connection type, elevator, vertical and which side of the pair */
+type DoorGroup<'a> = (&'a str, bool, bool, bool);
+
+fn pair_doors(ends: &[DoorEnd], rng: &mut Rng) -> Option<Vec<(usize, usize)>> {
+    let mut pairs = Vec::new();
+    if ends.is_empty() {
+        return Some(pairs);
+    }
+
+    let mut paired = vec![false; ends.len()];
+    let mut connected: HashSet<usize> = HashSet::new();
+    let all: HashSet<usize> = ends.iter().map(|e| e.component).collect();
+    connected.insert(ends[rng.range(ends.len())].component);
+
+    /* Grow the connected part one piece at a time through one of its open doors. A piece is only
+       added through its last open door if that still leaves some other open door to continue from. */
+    while connected.len() < all.len() {
+        let mut open = (0..ends.len()).filter(|&i| !paired[i] && connected.contains(&ends[i].component)).collect::<Vec<usize>>();
+        rng.shuffle(&mut open);
+
+        let open_count = open.len();
+        let last = connected.len() + 1 == all.len();
+        let free = |component: usize, paired: &[bool]| (0..ends.len()).filter(|&i| !paired[i] && ends[i].component == component).count();
+
+        let pair = open.iter().find_map(|&from| {
+            let candidates = (0..ends.len()).filter(|&to| {
+                !paired[to] && !connected.contains(&ends[to].component) && ends[from].fits(&ends[to])
+                    && (last || open_count + free(ends[to].component, &paired) > 2)
+            }).collect::<Vec<usize>>();
+            rng.choose(&candidates).map(|&to| (from, to))
+        })?;
+
+        paired[pair.0] = true;
+        paired[pair.1] = true;
+        connected.insert(ends[pair.1].component);
+        pairs.push(pair);
+    }
+
+    /* Everything is reachable now, so the doors that are left can go anywhere they fit */
+    let mut groups: BTreeMap<DoorGroup, Vec<usize>> = BTreeMap::new();
+    for (i, end) in ends.iter().enumerate().filter(|(i, _)| !paired[*i]) {
+        groups.entry((&end.connection_type, end.elevator, end.vertical, end.first)).or_default().push(i);
+    }
+
+    for ((connection_type, elevator, vertical, first), firsts) in &groups {
+        if *first {
+            let mut seconds = groups.get(&(connection_type, *elevator, *vertical, false)).cloned().unwrap_or_default();
+            if firsts.len() != seconds.len() {
+                return None;
+            }
+            rng.shuffle(&mut seconds);
+            pairs.extend(firsts.iter().copied().zip(seconds));
+        }
+    }
+
+    if pairs.len() * 2 != ends.len() {
+        return None;
+    }
+    Some(pairs)
+}
+
+#[derive(Default)]
+struct Components {
+    parents: HashMap<i64, i64>,
+    ids: HashMap<i64, usize>
+}
+
+impl Components {
+    fn root(&mut self, room: i64) -> i64 {
+        let parent = *self.parents.entry(room).or_insert(room);
+        if parent == room {
+            return room;
+        }
+        let root = self.root(parent);
+        self.parents.insert(room, root);
+        root
+    }
+
+    fn join(&mut self, a: i64, b: i64) {
+        let (a, b) = (self.root(a), self.root(b));
+        if a != b {
+            self.parents.insert(a, b);
+        }
+    }
+
+    fn find(&mut self, room: i64) -> usize {
+        let root = self.root(room);
+        let next = self.ids.len();
+        *self.ids.entry(root).or_insert(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn end(component: usize, position: &str) -> DoorEnd {
+        let node = ConnectionNode { area: "Crateria".to_string(), subarea: "Central".to_string(), roomid: component as i64, nodeid: 1, position: position.to_string() };
+        let (vertical, first) = match position {
+            "left" => (false, true),
+            "right" => (false, false),
+            "top" => (true, true),
+            _ => (true, false)
+        };
+        DoorEnd { node, connection_type: "Bidirectional".to_string(), elevator: false, vertical, first, component }
+    }
+
+    #[test]
+    fn pairs_form_one_component() {
+        /* A hub, dead ends with a single door and rooms in between */
+        let ends = vec![
+            end(0, "left"), end(0, "right"), end(0, "right"), end(0, "top"),
+            end(1, "left"),
+            end(2, "left"), end(2, "right"),
+            end(3, "left"), end(3, "bottom"),
+            end(4, "right"), end(4, "left"),
+            end(5, "right")
+        ];
+
+        let mut paired_seeds = 0;
+        for seed in 0..100 {
+            let pairs = match pair_doors(&ends, &mut Rng::new(seed)) {
+                Some(pairs) => pairs,
+                None => continue
+            };
+            paired_seeds += 1;
+
+            let mut used = vec![false; ends.len()];
+            let mut components = Components::default();
+            for &(a, b) in &pairs {
+                assert!(ends[a].fits(&ends[b]));
+                assert!(!used[a] && !used[b]);
+                used[a] = true;
+                used[b] = true;
+                components.join(ends[a].component as i64, ends[b].component as i64);
+            }
+            assert!(used.iter().all(|&u| u));
+
+            let root = components.root(0);
+            assert!((1..6).all(|c| components.root(c) == root), "seed {} left rooms unconnected", seed);
+        }
+        assert!(paired_seeds > 0);
+    }
+}
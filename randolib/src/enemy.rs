@@ -1,13 +1,55 @@
 use serde_derive::{Serialize,Deserialize};
+use crate::load_error::{LoadError, parse_json};
 use std::fs;
+use crate::weapon::Weapon;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Enemy {
     pub id: i64,
     pub name: String,
+    pub hp: i64,
+    pub attacks: Option<Vec<Attack>>,
+    pub damageMultipliers: Option<Vec<DamageMultiplier>>,
     pub invul: Option<Vec<String>>
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DamageMultiplier {
+    pub weapon: String,
+    pub value: f64
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Attack {
+    pub name: String,
+    pub baseDamage: i64
+}
+
+impl Enemy {
+    pub fn get_attack(&self, name: &str) -> Option<&Attack> {
+        self.attacks.iter().flatten().find(|a| a.name == name)
+    }
+
+    pub fn is_invulnerable(&self, weapon: &Weapon) -> bool {
+        self.invul.iter().flatten().any(|i| i == &weapon.name || weapon.categories.contains(i))
+    }
+
+    /* Number of shots it takes to kill this enemy with a weapon, or None if the weapon can't hurt it */
+    pub fn shots_to_kill(&self, weapon: &Weapon) -> Option<i64> {
+        if self.is_invulnerable(weapon) {
+            return None;
+        }
+
+        let multiplier = self.damageMultipliers.iter().flatten().find(|m| m.weapon == weapon.name).map(|m| m.value).unwrap_or(1.0);
+        let damage = weapon.damage as f64 * multiplier;
+        if damage <= 0.0 {
+            None
+        } else {
+            Some((self.hp as f64 / damage).ceil().max(1.0) as i64)
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EnemyFile {
     #[serde(rename="$schema")]
@@ -16,13 +58,49 @@ pub struct EnemyFile {
 }
 
 impl EnemyFile {
-    pub fn read(path: &str) -> Result<Vec<Enemy>, Box<dyn std::error::Error>> {
-        let data = fs::read_to_string(path)?;
-        let enemy_file: EnemyFile = serde_json::from_str(&data)?;
+    pub fn read(path: &str) -> Result<Vec<Enemy>, LoadError> {
+        let data = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+        EnemyFile::parse(&data, path)
+    }
+
+    pub fn parse(data: &str, path: &str) -> Result<Vec<Enemy>, LoadError> {
+        let enemy_file: EnemyFile = parse_json(data, path)?;
         if let Some(enemies) = enemy_file.enemies {
             Ok(enemies)
         } else {
-            bail!(format!("Could not find enemies node in enemy file: {:?}", path))
+            Err(LoadError::missing_section(path, "enemies"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EnemyFile;
+    use crate::load_error::LoadError;
+    use crate::test_world;
+
+    #[test]
+    fn missing_hp_fails_to_load() {
+        let data = r#"{"$schema": "", "enemies": [{"id": 1, "name": "Boss", "attacks": []}]}"#;
+        match EnemyFile::parse(data, "enemies/main.json") {
+            Err(LoadError::Parse { pointer, message, .. }) => {
+                assert_eq!(pointer, "/enemies/0");
+                assert!(message.contains("hp"));
+            },
+            other => panic!("expected a parse error, got {:?}", other)
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn shots_to_kill() {
+        let world = test_world::world();
+        let enemy = |name: &str| world.enemies.iter().find(|e| e.name == name).unwrap();
+        let weapon = |name: &str| world.weapons.iter().find(|w| w.name == name).unwrap();
+
+        assert_eq!(enemy("Boss").shots_to_kill(weapon("PowerBeam")), None);
+        assert_eq!(enemy("Boss").shots_to_kill(weapon("Missile")), Some(8));
+        assert_eq!(enemy("Boss").shots_to_kill(weapon("Super")), Some(2));
+        assert_eq!(enemy("Zoomer").shots_to_kill(weapon("PowerBeam")), Some(2));
+        assert_eq!(enemy("Zoomer").shots_to_kill(weapon("Super")), Some(1));
+    }
+}
@@ -0,0 +1,206 @@
+/* C API for the cdylib build, see include/randolib.h.
+   Strings going in are UTF-8 and null terminated, lists of names are comma separated.
+   Everything returned by the library has to be handed back to the matching free function.
+   Functions that can fail return null and leave a message for rando_last_error. */
+use crate::world::World;
+use crate::location::Location;
+use crate::fill::{self, FillAlgorithm, Placement};
+use crate::patch::PatchFormat;
+use crate::rom::Rom;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: String) {
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+unsafe fn read_str<'a>(s: *const c_char) -> Result<&'a str, Box<dyn std::error::Error>> {
+    if s.is_null() {
+        bail!("Got a null string")
+    }
+    Ok(CStr::from_ptr(s).to_str()?)
+}
+
+unsafe fn read_list<'a>(s: *const c_char) -> Result<Vec<&'a str>, Box<dyn std::error::Error>> {
+    if s.is_null() {
+        return Ok(Vec::new());
+    }
+    Ok(read_str(s)?.split(',').map(|i| i.trim()).filter(|i| !i.is_empty()).collect())
+}
+
+fn to_c_string(s: String) -> Result<*mut c_char, Box<dyn std::error::Error>> {
+    Ok(CString::new(s)?.into_raw())
+}
+
+/* Runs the body and turns an error into a null pointer with the message saved.
+   A panic must not unwind into C, so it is caught and reported the same way. */
+fn guard<T>(body: impl FnOnce() -> Result<*mut T, Box<dyn std::error::Error>>) -> *mut T {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => {
+            set_error(e.to_string());
+            ptr::null_mut()
+        },
+        Err(payload) => {
+            let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_error(format!("Internal error: {}", message));
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn world_ref<'a>(world: *const World) -> Result<&'a World, Box<dyn std::error::Error>> {
+    match world.as_ref() {
+        Some(world) => Ok(world),
+        None => bail!("Got a null world")
+    }
+}
+
+/// The message for the last failed call on this thread, or null. Free it with rando_string_free.
+#[no_mangle]
+pub extern "C" fn rando_last_error() -> *mut c_char {
+    LAST_ERROR.with(|e| match e.borrow_mut().take() {
+        Some(message) => CString::new(message).map(|s| s.into_raw()).unwrap_or(ptr::null_mut()),
+        None => ptr::null_mut()
+    })
+}
+
+/// Loads a world from an sm-json-data checkout. Free it with rando_world_free.
+///
+/// # Safety
+/// `name` and `path` must be valid null terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn rando_world_load(name: *const c_char, path: *const c_char) -> *mut World {
+    guard(|| {
+        let world = World::load_from(read_str(name)?, read_str(path)?)?;
+        Ok(Box::into_raw(Box::new(world)))
+    })
+}
+
+/// # Safety
+/// `world` must come from rando_world_load and not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn rando_world_free(world: *mut World) {
+    if !world.is_null() {
+        drop(Box::from_raw(world));
+    }
+}
+
+/// JSON array with the names of the item locations reachable from the start node.
+///
+/// # Safety
+/// `world` must come from rando_world_load, the other arguments must be valid null terminated strings or null for an empty list.
+#[no_mangle]
+pub unsafe extern "C" fn rando_reachable(world: *const World, items: *const c_char, techs: *const c_char, start: *const c_char) -> *mut c_char {
+    guard(|| {
+        let world = world_ref(world)?;
+        let items = read_list(items)?;
+        let start = read_str(start)?;
+        let (start_region, start_node) = match world.find_node(start) {
+            Some(start) => start,
+            None => bail!(format!("Could not find start location {}", start))
+        };
+
+        let flags = world.get_flags(&items, &read_list(techs)?);
+        let locations = Location::available(&flags, world, start_region, start_node).unwrap_or_default();
+        let names = locations.iter().map(|l| l.name.as_ref()).collect::<Vec<&str>>();
+        to_c_string(serde_json::to_string(&names)?)
+    })
+}
+
+/// Fills the vanilla item pool and returns the placement as JSON. Algorithm 0 is assumed fill and 1 is forward fill.
+///
+/// # Safety
+/// `world` must come from rando_world_load, `techs` and `start` must be valid null terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn rando_generate(world: *const World, techs: *const c_char, start: *const c_char, seed: u64, algorithm: u32) -> *mut c_char {
+    guard(|| {
+        let world = world_ref(world)?;
+        let start = read_str(start)?;
+        let (start_region, start_node) = match world.find_node(start) {
+            Some(start) => start,
+            None => bail!(format!("Could not find start location {}", start))
+        };
+        let algorithm = match algorithm {
+            0 => FillAlgorithm::Assumed,
+            1 => FillAlgorithm::Forward,
+            _ => bail!(format!("Unknown fill algorithm {}", algorithm))
+        };
+
+        let pool = world.get_item_pool();
+        let placement = fill::fill(world, &pool, &read_list(techs)?, start_region, start_node, seed, algorithm)?;
+        to_c_string(placement.to_json()?)
+    })
+}
+
+/// Applies a placement to a ROM and returns a patch against it. Format 0 is IPS and 1 is BPS.
+/// The length of the patch is written to `out_length`.
+///
+/// # Safety
+/// `world` must come from rando_world_load, `placement` must be a null terminated string,
+/// `rom` must point to `rom_length` readable bytes and `out_length` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn rando_create_patch(world: *const World, placement: *const c_char, rom: *const u8, rom_length: usize, format: u32, out_length: *mut usize) -> *mut u8 {
+    guard(|| {
+        let world = world_ref(world)?;
+        if rom.is_null() || out_length.is_null() {
+            bail!("Got a null buffer")
+        }
+        let placement = Placement::from_json(read_str(placement)?)?;
+        let format = match format {
+            0 => PatchFormat::Ips,
+            1 => PatchFormat::Bps,
+            _ => bail!(format!("Unknown patch format {}", format))
+        };
+
+        let original = Rom::from_bytes(std::slice::from_raw_parts(rom, rom_length).to_vec())?;
+        let mut patched = original.clone();
+        patched.apply_placement(world, &placement)?;
+        let patch = patched.create_patch(&original, format)?.into_boxed_slice();
+
+        *out_length = patch.len();
+        Ok(Box::into_raw(patch) as *mut u8)
+    })
+}
+
+/// # Safety
+/// `s` must be a string returned by this library or null.
+#[no_mangle]
+pub unsafe extern "C" fn rando_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// # Safety
+/// `buffer` and `length` must be a buffer and its length as returned by this library, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rando_buffer_free(buffer: *mut u8, length: usize) {
+    if !buffer.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer, length)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_become_errors() {
+        let result: *mut c_char = guard(|| panic!("boom"));
+        assert!(result.is_null());
+
+        let error = rando_last_error();
+        assert_eq!(unsafe { CStr::from_ptr(error) }.to_str().unwrap(), "Internal error: boom");
+        unsafe { rando_string_free(error) };
+    }
+}
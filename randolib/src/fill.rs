@@ -0,0 +1,248 @@
+use serde_derive::{Serialize, Deserialize};
+use crate::world::World;
+use crate::region::Region;
+use crate::node::{Node, NodeType};
+use crate::connection::Connection;
+use crate::settings::Settings;
+use crate::location::Location;
+use crate::rng::Rng;
+use crate::resources::RESOURCE_ITEMS;
+use std::collections::{HashMap, HashSet};
+
+const MAX_ATTEMPTS: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum FillAlgorithm {
+    Assumed,
+    Forward
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ItemPlacement {
+    pub region: i64,
+    pub node: i64,
+    pub location: String,
+    pub item: String
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Placement {
+    pub seed: u64,
+    pub algorithm: FillAlgorithm,
+    pub start_region: i64,
+    pub start_node: i64,
+    pub techs: Vec<String>,
+    pub items: Vec<ItemPlacement>,
+    /* The door connections the seed was made with, left out when the doors weren't shuffled */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connections: Option<Vec<Connection>>,
+    /* The resolved settings the seed was made with, so the logic can be set up the same way later */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<Settings>
+}
+
+impl Placement {
+    pub fn get_item(&self, region_id: i64, node_id: i64) -> Option<&str> {
+        self.items.iter().find(|p| p.region == region_id && p.node == node_id).map(|p| p.item.as_ref())
+    }
+
+    /* Where the player starts, needed when the start has been randomized */
+    pub fn get_start<'a>(&self, world: &'a World) -> Option<(&'a Region, &'a Node)> {
+        world.get_node(self.start_region, self.start_node)
+    }
+
+    pub fn get_techs(&self) -> Vec<&str> {
+        self.techs.iter().map(|t| t.as_ref()).collect()
+    }
+
+    /* Sets the world up the way it was when the seed was made, which has to happen before a playthrough or spoiler */
+    pub fn apply(&self, world: &mut World) {
+        if let Some(settings) = &self.settings {
+            settings.apply(world);
+        }
+        if let Some(connections) = &self.connections {
+            if *connections != world.connections {
+                world.set_connections(connections.clone());
+            }
+        }
+    }
+
+    /* Whether the world is set up for this placement, see apply */
+    pub fn is_applied(&self, world: &World) -> bool {
+        self.connections.as_ref().is_none_or(|c| *c == world.connections) && self.settings.as_ref().is_none_or(|s| s.is_applied(world))
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(data: &str) -> Result<Placement, serde_json::Error> {
+        serde_json::from_str(data)
+    }
+}
+
+/* Places every item in the pool on an item node, using Location::available to decide what can be reached.
+   The same world, pool, techs, start and seed always give the same placement. Shuffled doors have to be
+   set on the world beforehand and stored in the placement's connections afterwards, the same goes for the settings. */
+pub fn fill(world: &World, pool: &[&str], techs: &[&str], start_region: &Region, start_node: &Node, seed: u64, algorithm: FillAlgorithm) -> Result<Placement, Box<dyn std::error::Error>> {
+    let locations = world.regions.iter().flat_map(|r| r.nodes.iter().map(move |n| (r, n))).filter(|(_, n)| n.nodeType == Some(NodeType::Item)).map(|(r, n)| (r.id, n.id)).collect::<Vec<(i64, i64)>>();
+    if locations.len() != pool.len() {
+        bail!(format!("Item pool has {} items but the world has {} item locations", pool.len(), locations.len()));
+    }
+
+    let mut rng = Rng::new(seed);
+    let progression_names = world.get_progression_names();
+    let (progression, filler): (Vec<&str>, Vec<&str>) = pool.iter().partition(|i| progression_names.contains(*i) || RESOURCE_ITEMS.contains(i));
+
+    /* A fill can paint itself into a corner or leave the goal out of reach, so retry a few times.
+       The generator keeps running between attempts which keeps the result tied to the seed. */
+    let mut filler_state = Filler { world, techs, start_region, start_node, assigned: HashMap::new() };
+    let mut attempt = 1;
+    loop {
+        let result = filler_state.fill(&mut rng, &progression, &filler, &locations, algorithm).and_then(|_| {
+            let placement = filler_state.placement(seed, algorithm, &locations);
+            if world.is_beatable(&placement, start_region, start_node) {
+                Ok(placement)
+            } else {
+                bail!("Placement does not allow reaching the goal")
+            }
+        });
+
+        match result {
+            Ok(placement) => return Ok(placement),
+            Err(e) if attempt >= MAX_ATTEMPTS => return Err(e),
+            Err(_) => attempt += 1
+        }
+    }
+}
+
+struct Filler<'a> {
+    world: &'a World,
+    techs: &'a [&'a str],
+    start_region: &'a Region,
+    start_node: &'a Node,
+    assigned: HashMap<(i64, i64), &'a str>
+}
+
+impl<'a> Filler<'a> {
+    fn fill(&mut self, rng: &mut Rng, progression: &[&'a str], filler: &[&'a str], locations: &[(i64, i64)], algorithm: FillAlgorithm) -> Result<(), Box<dyn std::error::Error>> {
+        let mut progression = progression.to_vec();
+        let mut filler = filler.to_vec();
+        rng.shuffle(&mut progression);
+        rng.shuffle(&mut filler);
+        self.assigned = HashMap::new();
+
+        match algorithm {
+            FillAlgorithm::Assumed => self.assumed_fill(rng, progression)?,
+            FillAlgorithm::Forward => self.forward_fill(rng, progression)?
+        }
+
+        /* Filler items can't open anything up, so they just go into whatever is left */
+        let mut empty = locations.iter().filter(|l| !self.assigned.contains_key(l)).cloned().collect::<Vec<(i64, i64)>>();
+        rng.shuffle(&mut empty);
+        for (location, item) in empty.into_iter().zip(filler) {
+            self.assigned.insert(location, item);
+        }
+        Ok(())
+    }
+
+    fn placement(&self, seed: u64, algorithm: FillAlgorithm, locations: &[(i64, i64)]) -> Placement {
+        let items = locations.iter().map(|&(region, node)| {
+            let (_, n) = self.world.get_node(region, node).unwrap();
+            ItemPlacement { region, node, location: n.name.to_string(), item: self.assigned[&(region, node)].to_string() }
+        }).collect();
+
+        Placement {
+            seed,
+            algorithm,
+            start_region: self.start_region.id,
+            start_node: self.start_node.id,
+            techs: self.techs.iter().map(|t| t.to_string()).collect(),
+            items,
+            connections: None,
+            settings: None
+        }
+    }
+
+    /* Reachable locations when holding `items`, picking up everything already placed along the way */
+    fn reachable(&self, items: &[&'a str]) -> Vec<Location<'a>> {
+        let mut items = items.to_vec();
+        let mut collected: HashSet<(i64, i64)> = HashSet::new();
+
+        loop {
+            let flags = self.world.get_flags(&items, self.techs);
+            let locations = Location::available(&flags, self.world, self.start_region, self.start_node).unwrap_or_default();
+            let mut found = false;
+
+            for location in &locations {
+                let key = (location.region.id, location.node.id);
+                if let Some(item) = self.assigned.get(&key) {
+                    if collected.insert(key) {
+                        items.push(item);
+                        found = true;
+                    }
+                }
+            }
+
+            if !found {
+                return locations;
+            }
+        }
+    }
+
+    fn empty_locations(&self, locations: &[Location<'a>]) -> Vec<(i64, i64)> {
+        locations.iter().map(|l| (l.region.id, l.node.id)).filter(|l| !self.assigned.contains_key(l)).collect()
+    }
+
+    /* Each item is placed somewhere reachable while assuming we already hold every item that is still unplaced */
+    fn assumed_fill(&mut self, rng: &mut Rng, mut items: Vec<&'a str>) -> Result<(), Box<dyn std::error::Error>> {
+        while let Some(item) = items.pop() {
+            let empty = self.empty_locations(&self.reachable(&items));
+            match rng.choose(&empty) {
+                Some(&location) => { self.assigned.insert(location, item); },
+                None => bail!(format!("No reachable location left for {}", item))
+            }
+        }
+        Ok(())
+    }
+
+    /* Items are placed in reachable locations starting from nothing, preferring items that open up new locations */
+    fn forward_fill(&mut self, rng: &mut Rng, mut items: Vec<&'a str>) -> Result<(), Box<dyn std::error::Error>> {
+        while !items.is_empty() {
+            let empty = self.empty_locations(&self.reachable(&[]));
+            if empty.is_empty() {
+                bail!(format!("Ran out of reachable locations with {} items left to place", items.len()));
+            }
+
+            let index = (0..items.len())
+                .find(|&i| self.empty_locations(&self.reachable(&items[i..=i])).len() > empty.len())
+                .unwrap_or_else(|| rng.range(items.len()));
+
+            let item = items.remove(index);
+            let location = *rng.choose(&empty).unwrap();
+            self.assigned.insert(location, item);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fill, FillAlgorithm};
+    use crate::test_world;
+
+    #[test]
+    fn same_seed_same_placement() {
+        let world = test_world::world();
+        let pool = world.get_item_pool();
+        let (region, node) = world.get_node(8, 5).unwrap();
+        for &algorithm in &[FillAlgorithm::Assumed, FillAlgorithm::Forward] {
+            for seed in 0..20 {
+                let first = fill(&world, &pool, &[], region, node, seed, algorithm).unwrap();
+                let second = fill(&world, &pool, &[], region, node, seed, algorithm).unwrap();
+                assert_eq!(first, second);
+                assert_eq!(first.get_item(2, 2), Some("Morph"));
+            }
+        }
+    }
+}
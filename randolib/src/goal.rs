@@ -0,0 +1,59 @@
+use serde_derive::{Serialize, Deserialize};
+use crate::world::World;
+use crate::location::State;
+
+/* What has to happen for a seed to count as beaten: every listed event (as yielded by nodes, like boss flags)
+   has to be triggered and, if set, the target node given as [region id, node id] has to be reachable afterwards */
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Goal {
+    pub events: Vec<String>,
+    pub node: Option<(i64, i64)>
+}
+
+/* Defeat Mother Brain and escape to the ship in Landing Site, which is room 8, node 5 in sm-json-data */
+impl Default for Goal {
+    fn default() -> Goal {
+        Goal {
+            events: vec!["f_DefeatedMotherBrain".to_string()],
+            node: Some((8, 5))
+        }
+    }
+}
+
+impl Goal {
+    /* Checks a state left behind by Location::available_from_state */
+    pub fn is_met(&self, world: &World, state: &State) -> bool {
+        let events = self.events.iter().all(|e| world.compiled.names.id(e).is_some_and(|id| state.events.contains(id)));
+        let node = match self.node {
+            Some((region, node)) => world.graph.index(region, node).is_some_and(|i| state.nodes.contains_key(&i)),
+            None => true
+        };
+        events && node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::location::{Location, State};
+    use crate::resources::Resources;
+    use crate::test_world;
+
+    #[test]
+    fn default_goal_needs_mother_brain_and_the_ship() {
+        let mut world = test_world::world();
+        let beaten = |world: &crate::world::World, items: &[&str]| {
+            let (region, node) = world.get_node(8, 5).unwrap();
+            let items = world.get_flags(items, &[]);
+            let mut state = State::with_capacity(region.id, node.id, Resources::from_inventory(&items, &world.compiled.names));
+            Location::available_from_state(&items, world, region, node, &mut state);
+            world.goal.is_met(world, &state)
+        };
+
+        assert!(beaten(&world, &["Morph", "Missile"]));
+        assert!(!beaten(&world, &["Morph"]));
+
+        world.goal.node = Some((8, 2));
+        assert!(!beaten(&world, &["Missile"]));
+    }
+}
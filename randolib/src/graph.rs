@@ -0,0 +1,77 @@
+use crate::region::Region;
+use crate::connection::Connection;
+use std::collections::HashMap;
+
+/* Compiled view of the world used by the traversal.
+   Every node in every region gets a dense index, and in-room links and door connections are
+   resolved into adjacency lists up front so visiting a node never has to scan the raw data. */
+#[derive(Debug, Default)]
+pub struct Graph {
+    pub nodes: Vec<GraphNode>,
+    pub regions: HashMap<i64, usize>,
+    pub node_ids: HashMap<(i64, i64), usize>,
+    pub node_names: HashMap<String, usize>
+}
+
+#[derive(Debug)]
+pub struct GraphNode {
+    pub region: usize,
+    pub node: usize,
+    pub links: Vec<GraphLink>,
+    pub door: Option<usize>
+}
+
+/* Indexes into region.links[link].to[link_to] for the strats and requirements of the link */
+#[derive(Debug)]
+pub struct GraphLink {
+    pub to: usize,
+    pub link: usize,
+    pub link_to: usize
+}
+
+impl Graph {
+    pub fn build(regions: &[Region], connections: &[Connection]) -> Graph {
+        let mut graph = Graph::default();
+
+        for (ri, region) in regions.iter().enumerate() {
+            graph.regions.insert(region.id, ri);
+            for (ni, node) in region.nodes.iter().enumerate() {
+                let index = graph.nodes.len();
+                graph.nodes.push(GraphNode { region: ri, node: ni, links: Vec::new(), door: None });
+                graph.node_ids.insert((region.id, node.id), index);
+                graph.node_names.entry(node.name.to_string()).or_insert(index);
+            }
+        }
+
+        for region in regions {
+            for (li, link) in region.links.iter().enumerate() {
+                if let Some(&from) = graph.node_ids.get(&(region.id, link.from)) {
+                    for (ti, link_to) in link.to.iter().enumerate() {
+                        if let Some(&to) = graph.node_ids.get(&(region.id, link_to.id)) {
+                            graph.nodes[from].links.push(GraphLink { to, link: li, link_to: ti });
+                        }
+                    }
+                }
+            }
+        }
+
+        for connection in connections {
+            let ends = connection.nodes.iter().filter_map(|cn| graph.node_ids.get(&(cn.roomid, cn.nodeid)).copied()).collect::<Vec<usize>>();
+            if let [a, b] = ends[..] {
+                /* Like the old lookup, a door leads through the first connection that references it */
+                if graph.nodes[a].door.is_none() {
+                    graph.nodes[a].door = Some(b);
+                }
+                if graph.nodes[b].door.is_none() {
+                    graph.nodes[b].door = Some(a);
+                }
+            }
+        }
+
+        graph
+    }
+
+    pub fn index(&self, region_id: i64, node_id: i64) -> Option<usize> {
+        self.node_ids.get(&(region_id, node_id)).copied()
+    }
+}
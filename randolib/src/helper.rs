@@ -1,5 +1,6 @@
 use serde_derive::{Serialize,Deserialize};
 use crate::requirement::Requirement;
+use crate::load_error::{LoadError, parse_json};
 use std::fs;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -17,13 +18,17 @@ pub struct HelperFile {
 }
 
 impl HelperFile {
-    pub fn read(path: &str) -> Result<Vec<Helper>, Box<dyn std::error::Error>> {
-        let data = fs::read_to_string(path)?;
-        let helper_file: HelperFile = serde_json::from_str(&data)?;
+    pub fn read(path: &str) -> Result<Vec<Helper>, LoadError> {
+        let data = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+        HelperFile::parse(&data, path)
+    }
+
+    pub fn parse(data: &str, path: &str) -> Result<Vec<Helper>, LoadError> {
+        let helper_file: HelperFile = parse_json(data, path)?;
         if let Some(helpers) = helper_file.helpers {
             Ok(helpers)
         } else {
-            bail!(format!("Could not find helpers node in helper file: {:?}", path))
+            Err(LoadError::missing_section(path, "helpers"))
         }
     }
 }
\ No newline at end of file
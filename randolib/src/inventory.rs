@@ -0,0 +1,120 @@
+use crate::compiled::{Flags, Names};
+
+/* Everything we hold: items with how many copies of each, plus the helpers, techs and flags they give us.
+   Ids come from the Names of the world the inventory was made for. Items are interned before anything else,
+   so the counts stay short and an inventory is cheap to clone. */
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Inventory {
+    held: Flags,
+    counts: Vec<u32>
+}
+
+impl Inventory {
+    pub fn new() -> Inventory {
+        Inventory::default()
+    }
+
+    /* Item names can repeat for multiple tanks and expansions. Names the world doesn't know about are left out. */
+    pub fn from_items(items: &[&str], names: &Names) -> Inventory {
+        let mut inventory = Inventory::new();
+        for id in items.iter().filter_map(|i| names.id(i)) {
+            inventory.add(id);
+        }
+        inventory
+    }
+
+    /* Adds one copy of an item */
+    pub fn add(&mut self, id: usize) {
+        if self.counts.len() <= id {
+            self.counts.resize(id + 1, 0);
+        }
+        self.counts[id] += 1;
+        self.held.insert(id);
+    }
+
+    /* Marks a helper, tech or flag as held, these don't have copies */
+    pub fn insert(&mut self, id: usize) -> bool {
+        self.held.insert(id)
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.held.contains(id)
+    }
+
+    pub fn count(&self, id: usize) -> u32 {
+        match self.counts.get(id) {
+            Some(&count) if count > 0 => count,
+            _ => self.contains(id) as u32
+        }
+    }
+
+    /* Everything held in either inventory, with the larger count of each item */
+    pub fn union(&self, other: &Inventory) -> Inventory {
+        let mut held = self.held.clone();
+        held.union(&other.held);
+        let counts = (0..self.counts.len().max(other.counts.len()))
+            .map(|i| self.counts.get(i).copied().unwrap_or(0).max(other.counts.get(i).copied().unwrap_or(0)))
+            .collect();
+        Inventory { held, counts }
+    }
+
+    pub fn len(&self) -> usize {
+        self.held.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.held.iter()
+    }
+
+    pub fn names<'a>(&self, names: &'a Names) -> Vec<&'a str> {
+        self.iter().map(|id| names.name(id)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Inventory;
+    use crate::test_world;
+
+    #[test]
+    fn counts_copies() {
+        let world = test_world::world();
+        let names = &world.compiled.names;
+        let id = |name: &str| names.id(name).unwrap();
+
+        let inventory = Inventory::from_items(&["Missile", "Morph", "Missile", "Unknown"], names);
+        assert_eq!(inventory.count(id("Missile")), 2);
+        assert_eq!(inventory.count(id("Morph")), 1);
+        assert_eq!(inventory.count(id("Super")), 0);
+        assert_eq!(inventory.len(), 2);
+
+        /* Helpers, techs and flags count as one when held */
+        let mut inventory = Inventory::new();
+        assert!(inventory.insert(id("canWalljump")));
+        assert!(!inventory.insert(id("canWalljump")));
+        assert_eq!(inventory.count(id("canWalljump")), 1);
+    }
+
+    #[test]
+    fn union_keeps_the_larger_count() {
+        let world = test_world::world();
+        let names = &world.compiled.names;
+        let id = |name: &str| names.id(name).unwrap();
+
+        let mut first = Inventory::from_items(&["Missile", "Missile", "Morph"], names);
+        first.insert(id("canWalljump"));
+        let second = Inventory::from_items(&["Missile", "Super", "Super", "Super"], names);
+
+        let union = first.union(&second);
+        assert_eq!(union.count(id("Missile")), 2);
+        assert_eq!(union.count(id("Super")), 3);
+        assert_eq!(union.count(id("Morph")), 1);
+        assert!(union.contains(id("canWalljump")));
+        assert_eq!(union, second.union(&first));
+        assert_eq!(union.len(), 4);
+    }
+}
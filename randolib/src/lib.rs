@@ -4,7 +4,6 @@ extern crate serde;
 extern crate serde_derive;
 extern crate serde_json;
 extern crate walkdir;
-extern crate ffi_utils;
 
 #[macro_use]
 extern crate simple_error;
@@ -16,11 +15,35 @@ pub mod sparking;
 pub mod link;
 pub mod connection;
 pub mod world;
+pub mod source;
+pub mod load_error;
+pub mod validate;
+pub mod graph;
+pub mod compiled;
+pub mod inventory;
 pub mod location;
 pub mod weapon;
 pub mod enemy;
 pub mod helper;
 pub mod tech;
+pub mod rng;
+pub mod resources;
+pub mod damage;
+pub mod logic;
+pub mod goal;
+pub mod fill;
+pub mod start;
+pub mod settings;
+pub mod doors;
+pub mod playthrough;
+pub mod spoiler;
+pub mod patch;
+pub mod rom;
+pub mod ffi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(test)]
+mod test_world;
 
 pub fn load_world(name: &str, path: &str) -> Result<world::World, Box<dyn std::error::Error>>
 {
@@ -30,6 +30,18 @@ pub struct Strat
     pub note: Option<Note>
 }
 
+impl Strat
+{
+    pub fn collect_requirements<'a>(&'a self, reqs: &mut Vec<&'a Requirement>)
+    {
+        reqs.extend(self.requires.iter());
+        for obstacle in self.obstacles.iter().flatten() {
+            reqs.extend(obstacle.requires.iter());
+            reqs.extend(obstacle.bypass.iter());
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct Obstacle
 {
@@ -0,0 +1,125 @@
+use serde::de::DeserializeOwned;
+use serde_path_to_error::Segment;
+use crate::requirement::Requirement;
+use std::fmt;
+
+/* Everything that can go wrong while reading world data, with enough context to find the broken spot.
+   Pointers are JSON pointers into the file, like /rooms/3/links/0/to/1/strats/0/requires/2. */
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    Io { path: String, message: String },
+    MissingSection { path: String, section: String },
+    Parse { path: String, line: usize, column: usize, pointer: String, room: Option<String>, message: String },
+    UnknownRequirement { path: String, line: usize, column: usize, pointer: String, room: Option<String> }
+}
+
+impl LoadError {
+    pub fn io(path: &str, error: impl fmt::Display) -> LoadError {
+        LoadError::Io { path: path.to_string(), message: error.to_string() }
+    }
+
+    pub fn missing_section(path: &str, section: &str) -> LoadError {
+        LoadError::MissingSection { path: path.to_string(), section: section.to_string() }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            LoadError::Io { path, .. } | LoadError::MissingSection { path, .. } | LoadError::Parse { path, .. } | LoadError::UnknownRequirement { path, .. } => path
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let room = |room: &Option<String>| room.as_ref().map(|r| format!(" in room {}", r)).unwrap_or_default();
+        match self {
+            LoadError::Io { path, message } => write!(f, "{}: {}", path, message),
+            LoadError::MissingSection { path, section } => write!(f, "{}: missing \"{}\" section", path, section),
+            LoadError::Parse { path, line, column, pointer, room: r, message } => write!(f, "{}:{}:{}: {} at {}{}", path, line, column, message, pointer, room(r)),
+            LoadError::UnknownRequirement { path, line, column, pointer, room: r } => write!(f, "{}:{}:{}: unknown requirement at {}{}", path, line, column, pointer, room(r))
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/* Parses a data file, a failure points at the exact value that broke. Requirements are an untagged enum,
+   so when the failure is in a requirement list that has an entry serde can't match to any variant,
+   it is reported as an unknown requirement. */
+pub fn parse_json<T: DeserializeOwned>(data: &str, path: &str) -> Result<T, LoadError> {
+    let deserializer = &mut serde_json::Deserializer::from_str(data);
+    serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        let pointer = e.path().iter().map(|segment| match segment {
+            Segment::Seq { index } => format!("/{}", index),
+            Segment::Map { key } => format!("/{}", key.replace('~', "~0").replace('/', "~1")),
+            Segment::Enum { variant } => format!("/{}", variant),
+            Segment::Unknown => "/?".to_string()
+        }).collect::<String>();
+
+        let value: Option<serde_json::Value> = serde_json::from_str(data).ok();
+        let room = value.as_ref().and_then(|v| room_name(v, &pointer));
+        let inner = e.into_inner();
+        let (line, column) = (inner.line(), inner.column());
+        let message = inner.to_string();
+
+        match value.as_ref().and_then(|v| failing_requirement(v, &pointer)) {
+            Some(pointer) => LoadError::UnknownRequirement { path: path.to_string(), line, column, pointer, room },
+            None => {
+                /* serde_json adds the position to the message, it's already in the error itself */
+                let message = message.split(" at line ").next().unwrap_or_default().to_string();
+                LoadError::Parse { path: path.to_string(), line, column, pointer, room, message }
+            }
+        }
+    })
+}
+
+/* Name of the room a pointer like /rooms/3/... leads into */
+fn room_name(value: &serde_json::Value, pointer: &str) -> Option<String> {
+    let index = pointer.strip_prefix("/rooms/")?.split('/').next()?;
+    value.pointer(&format!("/rooms/{}/name", index))?.as_str().map(|n| n.to_string())
+}
+
+/* serde loses track of the position inside a requirement list, so find the entry that doesn't parse.
+   Requirements are names or objects, any other failing entry means the list isn't a requirement list. */
+fn failing_requirement(value: &serde_json::Value, pointer: &str) -> Option<String> {
+    let requirements = value.pointer(pointer)?.as_array()?;
+    let index = requirements.iter().position(|r| serde_json::from_value::<Requirement>(r.clone()).is_err())?;
+    if requirements[index].is_object() {
+        Some(format!("{}/{}", pointer, index))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LoadError;
+    use crate::region::RegionFile;
+
+    const ROOM: &str = r#"{"$schema": "", "rooms": [
+ {"id": 1, "name": "Test Room", "area": "Crateria", "subarea": "Central",
+  "nodes": [{"id": 1, "name": "Door"}, {"id": 2, "name": "Item"}],
+  "links": [{"from": 1, "to": [{"id": 2, "strats": [{"name": "Base", "notable": false, "requires": REQUIRES}]}]}]}
+]}"#;
+
+    #[test]
+    fn unknown_requirement() {
+        let data = ROOM.replace("REQUIRES", r#"["Morph", {"canFly": 5}]"#);
+        match RegionFile::parse(&data, "region/test.json") {
+            Err(LoadError::UnknownRequirement { pointer, room, .. }) => {
+                assert_eq!(pointer, "/rooms/0/links/0/to/0/strats/0/requires/1");
+                assert_eq!(room.as_deref(), Some("Test Room"));
+            },
+            other => panic!("expected an unknown requirement, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn bad_value_is_a_parse_error() {
+        let data = ROOM.replace("REQUIRES", r#"["Morph"]"#).replace(r#""notable": false"#, r#""notable": "no""#);
+        match RegionFile::parse(&data, "region/test.json") {
+            Err(LoadError::Parse { pointer, .. }) => assert_eq!(pointer, "/rooms/0/links/0/to/0/strats/0/notable"),
+            other => panic!("expected a parse error, got {:?}", other)
+        }
+    }
+}
@@ -1,31 +1,173 @@
 use crate::region::Region;
 use crate::node::{Node, NodeType};
 use crate::world::World;
-use crate::link::{LinkTo, Strat};
-use std::collections::{HashSet};
+use crate::resources::Resources;
+use crate::compiled::{Flags, CompiledRequirement, CompiledStrat, CompiledNode};
+use crate::inventory::Inventory;
+use std::collections::{HashMap, HashSet};
+
+/* How we got into the current room: the door node we came through, the longest usable runway
+   leading up to that door in the previous room and how many frames of shine charge we could bring along */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry
+{
+    pub node: i64,
+    pub runway: i64,
+    pub frames_remaining: Option<i64>
+}
+
+impl Entry
+{
+    pub fn dominates(&self, other: &Entry) -> bool
+    {
+        self.node == other.node && self.runway >= other.runway && self.frames_remaining >= other.frames_remaining
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Visit
+{
+    pub resources: Resources,
+    pub entry: Option<Entry>
+}
+
+impl Visit
+{
+    pub fn dominates(&self, other: &Visit) -> bool
+    {
+        self.resources.dominates(&other.resources) && match (&self.entry, &other.entry) {
+            (_, None) => true,
+            (Some(a), Some(b)) => a.dominates(b),
+            (None, Some(_)) => false
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct State
 {
-    pub events: HashSet<String>,
-    pub obstacles: HashSet<(i64, String)>,
-    pub nodes: HashSet<(i64, i64)>,
+    pub events: Flags,
+    pub obstacles: HashSet<(i64, usize)>,
+    pub unlocked: HashSet<(usize, usize)>,
+    pub nodes: HashMap<usize, Vec<Visit>>,
+    pub locations: HashSet<usize>,
     pub start: (i64, i64),
-    pub backtracking: bool
+    pub backtracking: bool,
+    pub capacity: Resources,
+    pub resources: Resources,
+    pub entry: Option<Entry>,
+    /* Obstacles in the order they were cleared, so a failed or discarded option can put them back */
+    cleared: Vec<(i64, usize)>
+}
+
+/* What rollback needs to undo everything done since the checkpoint was taken */
+#[derive(Debug, Clone, Copy)]
+struct Checkpoint {
+    resources: Resources,
+    cleared: usize
 }
 
 impl State
 {
     pub fn new(start_region: i64, start_node: i64) -> State
+    {
+        State::with_capacity(start_region, start_node, Resources::base())
+    }
+
+    pub fn with_capacity(start_region: i64, start_node: i64, capacity: Resources) -> State
     {
         State
         {
-            events: HashSet::new(),
+            events: Flags::new(),
             obstacles: HashSet::new(),
-            nodes: HashSet::new(),
+            unlocked: HashSet::new(),
+            nodes: HashMap::new(),
+            locations: HashSet::new(),
             start: (start_region, start_node),
-            backtracking: false
+            backtracking: false,
+            capacity,
+            resources: capacity,
+            entry: None,
+            cleared: Vec::new()
+        }
+    }
+
+    fn checkpoint(&self) -> Checkpoint
+    {
+        Checkpoint { resources: self.resources, cleared: self.cleared.len() }
+    }
+
+    fn rollback(&mut self, checkpoint: Checkpoint)
+    {
+        self.resources = checkpoint.resources;
+        for obstacle in self.cleared.drain(checkpoint.cleared..) {
+            self.obstacles.remove(&obstacle);
+        }
+    }
+
+    fn clear_obstacle(&mut self, obstacle: (i64, usize))
+    {
+        if self.obstacles.insert(obstacle) {
+            self.cleared.push(obstacle);
+        }
+    }
+
+    fn current_visit(&self) -> Visit
+    {
+        Visit { resources: self.resources, entry: self.entry }
+    }
+
+    /* A node is worth visiting again if we now arrive with resources or a room entry no earlier visit had */
+    pub fn can_visit(&self, index: usize) -> bool
+    {
+        let visit = self.current_visit();
+        match self.nodes.get(&index) {
+            Some(visits) => !visits.iter().any(|v| v.dominates(&visit)),
+            None => true
+        }
+    }
+
+    pub fn visit(&mut self, index: usize)
+    {
+        let visit = self.current_visit();
+        let visits = self.nodes.entry(index).or_default();
+        visits.retain(|v| !visit.dominates(v));
+        visits.push(visit);
+    }
+
+    /* Tries every option and keeps the one that leaves us with the most resources.
+       Only the obstacles cleared by that option stay cleared. */
+    pub fn cheapest<T>(&mut self, options: &[T], mut check: impl FnMut(&T, &mut State) -> bool) -> bool
+    {
+        let before = self.checkpoint();
+        let mut best: Option<(Resources, Vec<(i64, usize)>)> = None;
+        for option in options {
+            if check(option, self) && best.as_ref().is_none_or(|(b, _)| self.resources.total() > b.total()) {
+                best = Some((self.resources, self.cleared[before.cleared..].to_vec()));
+            }
+            self.rollback(before);
         }
+
+        match best {
+            Some((resources, cleared)) => {
+                self.resources = resources;
+                for obstacle in cleared {
+                    self.clear_obstacle(obstacle);
+                }
+                true
+            },
+            None => false
+        }
+    }
+
+    /* Starts a new pass over the graph, keeping events, cleared obstacles and unlocked doors from the previous one */
+    pub fn reset(&mut self)
+    {
+        self.nodes = HashMap::new();
+        self.locations = HashSet::new();
+        self.resources = self.capacity;
+        self.entry = None;
+        self.cleared = Vec::new();
     }
 }
 
@@ -40,38 +182,64 @@ pub struct Location<'a>
 impl<'a> Location<'a>
 {
 
-    pub fn available(items: &HashSet<String>, world: &'a World, from_region: &'a Region, from_node: &'a Node) -> Option<Vec<Location<'a>>>
+    /* Starts out with every tank and expansion in the inventory filled up */
+    pub fn available(items: &Inventory, world: &'a World, from_region: &'a Region, from_node: &'a Node) -> Option<Vec<Location<'a>>>
+    {
+        Location::available_with_capacity(items, &Resources::from_inventory(items, &world.compiled.names), world, from_region, from_node)
+    }
+
+    pub fn available_with_capacity(items: &Inventory, capacity: &Resources, world: &'a World, from_region: &'a Region, from_node: &'a Node) -> Option<Vec<Location<'a>>>
+    {
+        let mut state = State::with_capacity(from_region.id, from_node.id, *capacity);
+        Location::available_from_state(items, world, from_region, from_node, &mut state)
+    }
+
+    /* Keeps searching until a pass turns up no new events or unlocked doors.
+       The state is left as it was after the last pass, so callers can look at the events and nodes reached. */
+    pub fn available_from_state(items: &Inventory, world: &'a World, from_region: &'a Region, from_node: &'a Node, state: &mut State) -> Option<Vec<Location<'a>>>
     {
-        let mut state = State::new(from_region.id, from_node.id);
         let mut locations = Vec::new();
         let mut oldEvents = 0;
+        let mut oldUnlocked = 0;
         let mut pass = 1;
         
-        while pass == 1 || state.events.len() > oldEvents {
+        while pass == 1 || state.events.len() > oldEvents || state.unlocked.len() > oldUnlocked {
+            if pass > 1 {
+                state.reset();
+            }
             oldEvents = state.events.len();
+            oldUnlocked = state.unlocked.len();
             pass += 1;
-            locations = Location::visit_node(items, world, from_region, from_node, from_node, &mut state).unwrap_or_default();
-            state.nodes = HashSet::new();
+            locations = Location::available_with_state(items, world, from_region, from_node, state).unwrap_or_default();
         }
 
         Some(locations)
     }
 
-    pub fn available_with_state(items: &HashSet<String>, world: &'a World, from_region: &'a Region, from_node: &'a Node, state: &mut State) -> Option<Vec<Location<'a>>>
+    pub fn available_with_state(items: &Inventory, world: &'a World, from_region: &'a Region, from_node: &'a Node, state: &mut State) -> Option<Vec<Location<'a>>>
     {        
-        Some(Location::visit_node(items, world, from_region, from_node, from_node, state).unwrap_or_default())
+        let index = world.graph.index(from_region.id, from_node.id)?;
+        Some(Location::visit_node(items, world, index, state).unwrap_or_default())
     }
 
-    fn visit_node(items: &HashSet<String>, world: &'a World, region: &'a Region, node: &'a Node, _prevNode: &'a Node, state: &mut State) -> Option<Vec<Location<'a>>>
+    fn visit_node(items: &Inventory, world: &'a World, index: usize, state: &mut State) -> Option<Vec<Location<'a>>>
     {
+        let (region, node) = world.node_at(index);
+        let graph_node = &world.graph.nodes[index];
+        let compiled = &world.compiled.regions[graph_node.region].nodes[graph_node.node];
         //print!("Visiting {}: {}\n", &region.name, &node.name);
-        state.nodes.insert((region.id, node.id));
-        if let Some(yields) = &node.yields
-        {
-            if Location::can_unlock(items, world, region, node, state) {
-                state.events.extend(yields.iter().map(|y| y.to_string()));
+        state.visit(index);
+
+        let capacity = state.capacity;
+        for utility in node.utility.iter().flatten() {
+            state.resources.refill(utility, &capacity);
+        }
+
+        let unlocked = Location::can_unlock(items, world, index, region, compiled, state);
+        if unlocked {
+            for &event in &compiled.yields {
+                state.events.insert(event);
             }
-            //dbg!(&state.events);
         }
 
         let mut locations: Vec<Location> = Vec::new();
@@ -79,25 +247,28 @@ impl<'a> Location<'a>
         if let Some(nodeType) = &node.nodeType
         {
             /* If it's an item, add it to our item location collection */
-            if nodeType == &NodeType::Item && Location::can_unlock(items, world, region, node, state) {
+            if nodeType == &NodeType::Item && unlocked && !state.locations.contains(&index) {
                 if !state.backtracking {
                     let (start_region, start_node) = state.start;
                     let mut backtrack_state = state.clone();
-                    backtrack_state.nodes = HashSet::new();
+                    backtrack_state.nodes = HashMap::new();
+                    backtrack_state.locations = HashSet::new();
                     backtrack_state.obstacles = state.obstacles.iter().filter(|(o, _)| o == &region.id).cloned().collect();
+                    backtrack_state.cleared = Vec::new();
                     backtrack_state.start = (region.id, node.id);
                     backtrack_state.backtracking = true;
-                    if let Some(backtrack_locations) = Location::available_with_state(items, world, region, node, &mut backtrack_state) {
-                        if backtrack_locations.iter().any(|l| l.region.id == start_region && l.node.id == start_node) {
-                            let location = Location
-                            {
-                                name: node.name.to_string(),
-                                region,
-                                node,
-                            };
-        
-                            locations.push(location);    
-                        }
+                    /* The location only counts if we can make it back to where we started from */
+                    Location::available_with_state(items, world, region, node, &mut backtrack_state);
+                    if world.graph.index(start_region, start_node).is_some_and(|i| backtrack_state.nodes.contains_key(&i)) {
+                        let location = Location
+                        {
+                            name: node.name.to_string(),
+                            region,
+                            node,
+                        };
+    
+                        state.locations.insert(index);
+                        locations.push(location);    
                     }
                 } else {
                     let location = Location
@@ -107,39 +278,40 @@ impl<'a> Location<'a>
                         node,
                     };
 
+                    state.locations.insert(index);
                     locations.push(location);    
                 }
             }
 
-            /* If it's a door or exit, find the connecting node and visit it */
-            if (nodeType == &NodeType::Door || nodeType == &NodeType::Exit) && Location::can_unlock(items, world, region, node, state) {
-                if let Some(connection) = world.connections.iter().find(|c| c.nodes.iter().any(|cn| cn.roomid == region.id && cn.nodeid == node.id)) {
-                    if let Some(connection_node) = connection.nodes.iter().find(|cn| !(cn.roomid == region.id && cn.nodeid == node.id)) {
-                        if let Some(target_region) = world.regions.iter().find(|r| r.id == connection_node.roomid) {
-                            if let Some(target_node) = target_region.nodes.iter().find(|n| n.id == connection_node.nodeid) {
-                                if !state.nodes.contains(&(target_region.id, target_node.id)) {
-                                    if let Some(mut new_locations) = Location::visit_node(items, world, target_region, target_node, node, state) {
-                                        locations.append(&mut new_locations);
-                                    }
-                                }
-                            }
+            /* If it's a door or exit, follow the connection to the node on the other side and visit it */
+            if (nodeType == &NodeType::Door || nodeType == &NodeType::Exit) && unlocked {
+                if let Some(target) = world.graph.nodes[index].door {
+                    let resources = state.resources;
+                    let entry = state.entry;
+                    state.entry = Some(Location::leave_through(items, world, region, compiled, world.node_at(target).1, state));
+                    if state.can_visit(target) {
+                        if let Some(mut new_locations) = Location::visit_node(items, world, target, state) {
+                            locations.append(&mut new_locations);
                         }
                     }
-                }             
+                    state.resources = resources;
+                    state.entry = entry;
+                }
             }
         }
 
-        /* Find the in-room links for this node */
-        if let Some(links) = region.links.iter().find(|l| l.from == node.id) {
-            for link in &links.to {
-                if let Some(link_node) = region.nodes.iter().find(|n| n.id == link.id) {
-                    if !state.nodes.contains(&(region.id, link_node.id)) && Location::can_traverse(items, world, region, &link, state) && Location::can_access(items, world, region, &link_node, state) {
-                        if let Some(mut new_locations) = Location::visit_node(items, world, region, link_node, node, state) {
-                            locations.append(&mut new_locations);
-                        }
-                    }                     
+        /* Follow the in-room links for this node, each branch starts out with the resources we arrived here with */
+        for graph_link in &graph_node.links {
+            let strats = &world.compiled.regions[graph_node.region].links[graph_link.link][graph_link.link_to];
+            let link_node = &world.compiled.regions[graph_node.region].nodes[world.graph.nodes[graph_link.to].node];
+            let resources = state.resources;
+            /* Paying for a link only lowers our resources, so skip links that already lead somewhere we've been with more */
+            if state.can_visit(graph_link.to) && Location::can_traverse(items, world, region, strats, state) && Location::can_access(items, world, link_node, state) && state.can_visit(graph_link.to) {
+                if let Some(mut new_locations) = Location::visit_node(items, world, graph_link.to, state) {
+                    locations.append(&mut new_locations);
                 }
             }
+            state.resources = resources;
         }
         
         if !locations.is_empty() {
@@ -149,68 +321,175 @@ impl<'a> Location<'a>
         }
     }
 
-    fn can_unlock(items: &HashSet<String>, world: &World, region: &Region, node: &Node, state: &mut State) -> bool {
-        match &node.locks {
-            Some(locks) => locks.iter().all(|lock|
-                match &lock.lock {
-                    Some(_r) => true,
-                    None => 
-                        (match &lock.unlockStrats {
-                            Some(us) => us.iter().any(|s| Location::can_do_strat(items, world, region, s, state)),
-                            None => true
-                        } ||
-                        match &lock.bypassStrats {
-                            Some(bs) => bs.iter().any(|s| Location::can_do_strat(items, world, region, s, state)),
-                            None => false
-                        })                    
+    /* Every active lock on the node has to be opened or bypassed. A lock is active when its `lock` requirement holds
+       (or it has none), opening it through an unlock strat keeps it open for the rest of the search while bypassing only works this once */
+    fn can_unlock(items: &Inventory, world: &World, index: usize, region: &Region, node: &CompiledNode, state: &mut State) -> bool {
+        let checkpoint = state.checkpoint();
+        let unlocked = node.locks.iter().enumerate().all(|(li, lock)| {
+            if state.unlocked.contains(&(index, li)) || !Location::is_lock_active(items, world, &lock.lock, state) {
+                return true;
+            }
+
+            let unlocked = match &lock.unlock_strats {
+                Some(us) => state.cheapest(us, |s, state| Location::can_do_strat(items, world, region, s, state)),
+                None => true
+            };
+
+            if unlocked {
+                state.unlocked.insert((index, li));
+                true
+            } else {
+                match &lock.bypass_strats {
+                    Some(bs) => state.cheapest(bs, |s, state| Location::can_do_strat(items, world, region, s, state)),
+                    None => false
                 }
-            ),
+            }
+        });
+
+        if !unlocked {
+            state.rollback(checkpoint);
+        }
+        unlocked
+    }
+
+    fn is_lock_active(items: &Inventory, world: &World, lock: &Option<CompiledRequirement>, state: &mut State) -> bool {
+        match lock {
+            Some(r) => {
+                let resources = state.resources;
+                let active = r.check(items, world, state);
+                state.resources = resources;
+                active
+            },
             None => true
         }
     }
 
-    fn can_access(items: &HashSet<String>, world: &World, _region: &Region, node: &Node, state: &mut State) -> bool {
-        match &node.interactionRequires {
+    /* Works out what we can bring along when leaving the room through a door: the runway leading up to it
+       and the frames left on a shine charge gained in this room */
+    fn leave_through(items: &Inventory, world: &World, region: &Region, node: &CompiledNode, target: &Node, state: &mut State) -> Entry {
+        let checkpoint = state.checkpoint();
+        let can_do_strats = |strats: &Option<Vec<CompiledStrat>>, state: &mut State| match strats {
+            Some(strats) => state.cheapest(strats, |s, state| Location::can_do_strat(items, world, region, s, state)),
+            None => true
+        };
+
+        let runway = node.runways.iter().filter(|r| can_do_strats(&r.strats, state)).map(|r| r.length).max().unwrap_or(0);
+        let frames_remaining = if items.contains(world.compiled.speed_booster) {
+            node.leave_charged.iter()
+                .filter(|c| c.used_tiles >= world.logic.shine_charge_tiles && can_do_strats(&c.strats, state))
+                .map(|c| c.frames_remaining)
+                .max()
+        } else {
+            None
+        };
+
+        state.rollback(checkpoint);
+        Entry { node: target.id, runway, frames_remaining }
+    }
+
+    fn can_access(items: &Inventory, world: &World, node: &CompiledNode, state: &mut State) -> bool {
+        match &node.interaction {
             Some(r) => r.check(items, world, state),
             None => true
         }
     }
 
-    fn can_traverse(items: &HashSet<String>, world: &World, region: &Region, link: &LinkTo, state: &mut State) -> bool {        
-        match &link.strats {
-            Some(strats) => strats.iter().any(|s| Location::can_do_strat(items, world, region, s, state)),
+    fn can_traverse(items: &Inventory, world: &World, region: &Region, strats: &Option<Vec<CompiledStrat>>, state: &mut State) -> bool {
+        match strats {
+            Some(strats) => state.cheapest(strats, |s, state| Location::can_do_strat(items, world, region, s, state)),
             None => true
         }
     }
 
-    fn can_do_strat(items: &HashSet<String>, world: &World, region: &Region, strat: &Strat, state: &mut State) -> bool {
+    fn can_do_strat(items: &Inventory, world: &World, region: &Region, strat: &CompiledStrat, state: &mut State) -> bool {
+        if strat.notable && !world.logic.is_notable_enabled(&region.name, &strat.name) {
+            return false;
+        }
+
+        let checkpoint = state.checkpoint();
         let requires = match &strat.requires {
             Some(r) => r.check(items, world, state),
             None => true
         };
+        if !requires {
+            return false;
+        }
 
-        let obstacles = match &strat.obstacles {
-            Some(ob) => {
-                ob.iter().all(|o| state.obstacles.contains(&(region.id, o.id.as_ref().unwrap().to_string())) ||
-                    (match &o.requires {
-                        Some(r) => {
-                            if r.check(items, world, state) {
-                                state.obstacles.insert((region.id, o.id.as_ref().unwrap().to_string())) && true
-                            } else {
-                                false
-                            }
-                        },
-                        None => true
-                    } || 
-                    match &o.bypass {
-                        Some(b) => b.check(items, world, state),
-                        None => false
-                    })
-                )                                
-            },
-            None => true
-        };
+        /* An obstacle is passed if it is already cleared, can be cleared now or can be bypassed */
+        let obstacles = strat.obstacles.iter().flatten().all(|o| {
+            let obstacle = (region.id, o.index);
+            if state.obstacles.contains(&obstacle) {
+                return true;
+            }
+            match &o.requires {
+                Some(r) if r.check(items, world, state) => {
+                    state.clear_obstacle(obstacle);
+                    true
+                },
+                Some(_) => o.bypass.as_ref().is_some_and(|b| b.check(items, world, state)),
+                None => true
+            }
+        });
 
-        requires && obstacles
+        if !obstacles {
+            state.rollback(checkpoint);
+        }
+        obstacles
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::Location;
+    use crate::test_world;
+
+    /* Node 2 is behind a strat that needs Bombs and clears obstacle A with a Power Bomb, or a free strat.
+       Node 3 can only be reached once obstacle A has been cleared. */
+    const OBSTACLE_ROOM: &str = r#"{"$schema": "", "rooms": [
+ {"id": 20, "name": "Obstacle Room", "area": "Crateria", "subarea": "Central",
+  "obstacles": [{"id": "A", "name": "Bomb Blocks"}],
+  "nodes": [
+   {"id": 1, "name": "Obstacle Room Start", "nodeType": "junction", "nodeSubType": "junction"},
+   {"id": 2, "name": "Obstacle Room Middle", "nodeType": "item", "nodeSubType": "visible", "nodeItem": "Bombs"},
+   {"id": 3, "name": "Obstacle Room Behind Blocks", "nodeType": "item", "nodeSubType": "visible", "nodeItem": "Super"}
+  ],
+  "links": [
+   {"from": 1, "to": [
+    {"id": 2, "strats": [
+     {"name": "Bomb Through", "notable": false, "requires": ["Bombs"], "obstacles": [{"id": "A", "requires": [{"ammo": {"type": "PowerBomb", "count": 1}}]}]},
+     STRAT
+    ]},
+    {"id": 3, "strats": [{"name": "Through Cleared Blocks", "notable": false, "requires": [], "obstacles": [{"id": "A", "requires": ["Varia"]}]}]}
+   ]},
+   {"from": 2, "to": [{"id": 1, "strats": [{"name": "Base", "notable": false, "requires": []}]}]},
+   {"from": 3, "to": [{"id": 1, "strats": [{"name": "Base", "notable": false, "requires": []}]}]}
+  ]}
+]}"#;
+
+    fn reachable(strat: &str, items: &[&str]) -> Vec<String> {
+        let room = OBSTACLE_ROOM.replace("STRAT", strat);
+        let world = test_world::world_with(&[("region/crateria/obstacle.json", &room)]);
+        let (region, node) = world.get_node(20, 1).unwrap();
+        let items = world.get_flags(items, &[]);
+        let mut names = Location::available(&items, &world, region, node).unwrap_or_default().into_iter().map(|l| l.name).collect::<Vec<String>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn failed_strat_leaves_obstacles_alone() {
+        let impossible = r#"{"name": "Never", "notable": false, "requires": ["Varia"]}"#;
+        assert!(reachable(impossible, &["PowerBomb"]).is_empty());
+    }
+
+    #[test]
+    fn discarded_strat_leaves_obstacles_alone() {
+        let free = r#"{"name": "Walk", "notable": false, "requires": []}"#;
+        assert_eq!(reachable(free, &["PowerBomb", "Bombs"]), vec!["Obstacle Room Middle"]);
+    }
+
+    #[test]
+    fn picked_strat_clears_obstacles() {
+        let impossible = r#"{"name": "Never", "notable": false, "requires": ["Varia"]}"#;
+        assert_eq!(reachable(impossible, &["PowerBomb", "Bombs"]), vec!["Obstacle Room Behind Blocks", "Obstacle Room Middle"]);
+    }
+}
@@ -0,0 +1,104 @@
+use serde_derive::{Serialize, Deserialize};
+use crate::damage::{self, DamageTable, Leniency, Environment, Suit};
+use std::collections::BTreeMap;
+
+/* Tunable rules the traversal applies on top of the world data */
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Logic {
+    pub damage: DamageTable,
+    pub leniency: Leniency,
+    /* Shortest runway, in tiles, a player is expected to gain a shine charge on */
+    pub shine_charge_tiles: i64,
+    /* Whether notable strats can be used when nothing more specific is set */
+    pub notable_strats: bool,
+    /* Turns single notable strats on or off, keyed by room name and then strat name */
+    pub strat_toggles: BTreeMap<String, BTreeMap<String, bool>>
+}
+
+impl Default for Logic {
+    fn default() -> Logic {
+        Logic {
+            damage: DamageTable::default(),
+            leniency: Leniency::default(),
+            shine_charge_tiles: 25,
+            notable_strats: true,
+            strat_toggles: BTreeMap::new()
+        }
+    }
+}
+
+impl Logic {
+    /* Non-notable strats are always allowed, notable ones follow their toggle or the notable strat default */
+    pub fn is_notable_enabled(&self, room: &str, strat: &str) -> bool {
+        self.strat_toggles.get(room).and_then(|s| s.get(strat)).copied().unwrap_or(self.notable_strats)
+    }
+
+    pub fn environment_damage(&self, environment: Environment, amount: i64, suit: Suit) -> i64 {
+        let damage = self.damage.get(environment).get_damage(amount, suit) * self.leniency.get(environment);
+        damage.ceil() as i64
+    }
+
+    pub fn enemy_damage(&self, base_damage: i64, hits: i64, suit: Suit) -> i64 {
+        let damage = (damage::suit_damage(base_damage, suit) * hits) as f64 * self.leniency.enemy_damage;
+        damage.ceil() as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Logic;
+    use crate::damage::{Environment, Suit};
+    use crate::test_world;
+
+    /* Suit for each combination of Varia and Gravity, Gravity takes over when both are held */
+    fn suits() -> Vec<(&'static str, Suit)> {
+        let world = test_world::world();
+        [("none", &[][..]), ("varia", &["Varia"][..]), ("gravity", &["Gravity"][..]), ("both", &["Varia", "Gravity"][..])].iter()
+            .map(|(name, items)| (*name, world.compiled.suit(&world.inventory(items))))
+            .collect()
+    }
+
+    #[test]
+    fn suit_combinations() {
+        assert_eq!(suits(), vec![("none", Suit::Power), ("varia", Suit::Varia), ("gravity", Suit::Gravity), ("both", Suit::Gravity)]);
+    }
+
+    #[test]
+    fn heat_damage() {
+        let logic = Logic::default();
+        let expected = [("none", 25), ("varia", 0), ("gravity", 0), ("both", 0)];
+        for ((name, suit), (_, damage)) in suits().into_iter().zip(expected.iter()) {
+            assert_eq!(logic.environment_damage(Environment::Heat, 100, suit), *damage, "{}", name);
+        }
+    }
+
+    #[test]
+    fn lava_damage() {
+        let logic = Logic::default();
+        let expected = [("none", 50), ("varia", 50), ("gravity", 0), ("both", 0)];
+        for ((name, suit), (_, damage)) in suits().into_iter().zip(expected.iter()) {
+            assert_eq!(logic.environment_damage(Environment::Lava, 100, suit), *damage, "{}", name);
+        }
+    }
+
+    #[test]
+    fn leniency_scales_damage() {
+        let mut logic = Logic::default();
+        logic.leniency.heat = 1.5;
+        logic.leniency.lava = 2.0;
+        logic.leniency.enemy_damage = 1.25;
+
+        /* 100 heat frames at 0.25 each is 37.5 with leniency, rounded up */
+        assert_eq!(logic.environment_damage(Environment::Heat, 100, Suit::Power), 38);
+        assert_eq!(logic.environment_damage(Environment::Heat, 100, Suit::Varia), 0);
+        assert_eq!(logic.environment_damage(Environment::Lava, 100, Suit::Varia), 100);
+        assert_eq!(logic.environment_damage(Environment::Lava, 100, Suit::Gravity), 0);
+        assert_eq!(logic.environment_damage(Environment::Acid, 10, Suit::Power), 15);
+
+        /* Enemy damage is halved by Varia and quartered by Gravity before leniency */
+        assert_eq!(logic.enemy_damage(60, 2, Suit::Power), 150);
+        assert_eq!(logic.enemy_damage(60, 2, Suit::Varia), 75);
+        assert_eq!(logic.enemy_damage(60, 2, Suit::Gravity), 38);
+    }
+}
@@ -24,6 +24,15 @@ pub struct Node
     pub note: Option<Note>
 }
 
+impl Node
+{
+    /* Runways can be listed directly on the node or under its sparking data */
+    pub fn get_runways(&self) -> impl Iterator<Item = &Runway>
+    {
+        self.runways.iter().flatten().chain(self.sparking.iter().flat_map(|s| s.runways.iter().flatten()))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct Lock
 {
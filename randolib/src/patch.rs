@@ -0,0 +1,432 @@
+use serde_derive::{Serialize, Deserialize};
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_FOOTER: &[u8] = b"EOF";
+/* A record starting here would be read as the footer */
+const IPS_EOF_OFFSET: usize = 0x454F46;
+const IPS_MAX_OFFSET: usize = 0xFFFFFF;
+const IPS_MAX_RECORD: usize = 0xFFFF;
+
+const BPS_MAGIC: &[u8] = b"BPS1";
+/* Unchanged stretches shorter than this are cheaper to write out as part of the changed bytes around them */
+const BPS_MIN_SOURCE_READ: usize = 4;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum PatchFormat {
+    Ips,
+    Bps
+}
+
+pub fn create(format: PatchFormat, source: &[u8], target: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    match format {
+        PatchFormat::Ips => create_ips(source, target),
+        PatchFormat::Bps => Ok(create_bps(source, target))
+    }
+}
+
+/* Applies an IPS or BPS patch, the format is picked from the patch header */
+pub fn apply(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if patch.starts_with(IPS_MAGIC) {
+        apply_ips(source, patch)
+    } else if patch.starts_with(BPS_MAGIC) {
+        apply_bps(source, patch)
+    } else {
+        bail!("Unknown patch format")
+    }
+}
+
+pub fn create_ips(source: &[u8], target: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if target.len() > IPS_MAX_OFFSET + 1 {
+        bail!(format!("Target of {} bytes is too large for an IPS patch", target.len()))
+    }
+
+    let differs = |i: usize| source.get(i) != Some(&target[i]);
+    let mut patch = IPS_MAGIC.to_vec();
+    let mut i = 0;
+
+    while i < target.len() {
+        if !differs(i) {
+            i += 1;
+            continue;
+        }
+
+        let start = if i == IPS_EOF_OFFSET { i - 1 } else { i };
+        let mut end = i;
+        while end < target.len() && end - start < IPS_MAX_RECORD && differs(end) {
+            end += 1;
+        }
+
+        let data = &target[start..end];
+        patch.extend_from_slice(&(start as u32).to_be_bytes()[1..]);
+        if data.len() > 3 && data.iter().all(|&b| b == data[0]) {
+            patch.extend_from_slice(&[0, 0]);
+            patch.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            patch.push(data[0]);
+        } else {
+            patch.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            patch.extend_from_slice(data);
+        }
+        i = end;
+    }
+
+    patch.extend_from_slice(IPS_FOOTER);
+    if target.len() < source.len() {
+        patch.extend_from_slice(&(target.len() as u32).to_be_bytes()[1..]);
+    }
+    Ok(patch)
+}
+
+pub fn apply_ips(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if !patch.starts_with(IPS_MAGIC) {
+        bail!("Patch is not an IPS patch")
+    }
+
+    let mut reader = Reader { data: patch, position: IPS_MAGIC.len() };
+    let mut target = source.to_vec();
+
+    loop {
+        if reader.remaining().starts_with(IPS_FOOTER) {
+            reader.read(IPS_FOOTER.len())?;
+            if reader.remaining().len() >= 3 {
+                let size = reader.read_be(3)?;
+                target.truncate(size);
+            }
+            return Ok(target);
+        }
+
+        let offset = reader.read_be(3)?;
+        let (data, length) = match reader.read_be(2)? {
+            0 => {
+                let length = reader.read_be(2)?;
+                (None, length)
+            },
+            length => (Some(reader.read(length)?), length)
+        };
+
+        if target.len() < offset + length {
+            target.resize(offset + length, 0);
+        }
+
+        match data {
+            Some(data) => target[offset..offset + length].copy_from_slice(data),
+            None => {
+                let value = reader.read(1)?[0];
+                target[offset..offset + length].iter_mut().for_each(|b| *b = value);
+            }
+        }
+    }
+}
+
+/* Only uses source and target reads, which is all a ROM where bytes change in place needs */
+pub fn create_bps(source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut patch = BPS_MAGIC.to_vec();
+    write_number(&mut patch, source.len() as u64);
+    write_number(&mut patch, target.len() as u64);
+    write_number(&mut patch, 0);
+
+    let same = |i: usize| source.get(i) == Some(&target[i]);
+    let mut literal_start = 0;
+    let mut i = 0;
+
+    while i < target.len() {
+        let mut end = i;
+        while end < target.len() && same(end) {
+            end += 1;
+        }
+
+        if end - i >= BPS_MIN_SOURCE_READ || (end == target.len() && end > i) {
+            write_target_read(&mut patch, &target[literal_start..i]);
+            write_number(&mut patch, ((end - i - 1) << 2) as u64);
+            literal_start = end;
+            i = end;
+        } else {
+            i = end.max(i + 1);
+        }
+    }
+    write_target_read(&mut patch, &target[literal_start..]);
+
+    patch.extend_from_slice(&crc32(source).to_le_bytes());
+    patch.extend_from_slice(&crc32(target).to_le_bytes());
+    let patch_crc = crc32(&patch);
+    patch.extend_from_slice(&patch_crc.to_le_bytes());
+    patch
+}
+
+fn write_target_read(patch: &mut Vec<u8>, data: &[u8]) {
+    if !data.is_empty() {
+        write_number(patch, (((data.len() - 1) << 2) | 1) as u64);
+        patch.extend_from_slice(data);
+    }
+}
+
+pub fn apply_bps(source: &[u8], patch: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    if !patch.starts_with(BPS_MAGIC) || patch.len() < BPS_MAGIC.len() + 12 {
+        bail!("Patch is not a BPS patch")
+    }
+
+    let footer = patch.len() - 12;
+    let checksum = |at: usize| u32::from_le_bytes([patch[at], patch[at + 1], patch[at + 2], patch[at + 3]]);
+    if crc32(&patch[..footer + 8]) != checksum(footer + 8) {
+        bail!("BPS patch is corrupt")
+    }
+    if crc32(source) != checksum(footer) {
+        bail!("BPS patch does not match the source ROM")
+    }
+
+    let mut reader = Reader { data: &patch[..footer], position: BPS_MAGIC.len() };
+    let source_size = reader.read_number()? as usize;
+    let target_size = reader.read_number()? as usize;
+    let metadata_size = reader.read_number()? as usize;
+    reader.read(metadata_size)?;
+
+    if source_size != source.len() {
+        bail!(format!("BPS patch expects a source of {} bytes but got {}", source_size, source.len()))
+    }
+
+    /* The sizes come from the patch, so nothing is trusted until it has been checked against what is already there */
+    let mut target: Vec<u8> = Vec::with_capacity(target_size.min(source.len() + patch.len()));
+    let mut source_offset: i64 = 0;
+    let mut target_offset: i64 = 0;
+
+    while !reader.remaining().is_empty() {
+        let command = reader.read_number()?;
+        let length = (command >> 2) as usize + 1;
+        if target.len().checked_add(length).is_none_or(|end| end > target_size) {
+            bail!("BPS patch writes past the end of the target")
+        }
+
+        match command & 3 {
+            0 => {
+                let start = target.len();
+                match source.get(start..start + length) {
+                    Some(data) => target.extend_from_slice(data),
+                    None => bail!("BPS source read is out of range")
+                }
+            },
+            1 => target.extend_from_slice(reader.read(length)?),
+            2 => {
+                source_offset = source_offset.checked_add(reader.read_offset()?).ok_or("BPS source copy is out of range")?;
+                if source_offset < 0 {
+                    bail!("BPS source copy is out of range")
+                }
+                let start = source_offset as usize;
+                match start.checked_add(length).and_then(|end| source.get(start..end)) {
+                    Some(data) => target.extend_from_slice(data),
+                    None => bail!("BPS source copy is out of range")
+                }
+                source_offset += length as i64;
+            },
+            _ => {
+                target_offset = target_offset.checked_add(reader.read_offset()?).ok_or("BPS target copy is out of range")?;
+                if target_offset < 0 || target_offset as usize >= target.len() {
+                    bail!("BPS target copy is out of range")
+                }
+                /* The copy may overlap what it writes, so it has to go byte by byte */
+                for _ in 0..length {
+                    target.push(target[target_offset as usize]);
+                    target_offset += 1;
+                }
+            }
+        }
+    }
+
+    if target.len() != target_size || crc32(&target) != checksum(footer + 4) {
+        bail!("BPS patch produced the wrong output")
+    }
+    Ok(target)
+}
+
+fn write_number(patch: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let x = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            patch.push(0x80 | x);
+            return;
+        }
+        patch.push(x);
+        value -= 1;
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> &'a [u8] {
+        &self.data[self.position..]
+    }
+
+    fn read(&mut self, length: usize) -> Result<&'a [u8], Box<dyn std::error::Error>> {
+        match self.data.get(self.position..self.position + length) {
+            Some(data) => {
+                self.position += length;
+                Ok(data)
+            },
+            None => bail!("Unexpected end of patch")
+        }
+    }
+
+    fn read_be(&mut self, length: usize) -> Result<usize, Box<dyn std::error::Error>> {
+        Ok(self.read(length)?.iter().fold(0, |a, &b| (a << 8) | b as usize))
+    }
+
+    fn read_number(&mut self) -> Result<u64, Box<dyn std::error::Error>> {
+        let mut value: u64 = 0;
+        let mut shift: u64 = 1;
+        loop {
+            let x = self.read(1)?[0];
+            value = ((x & 0x7F) as u64).checked_mul(shift).and_then(|v| value.checked_add(v)).ok_or("Number in patch is too large")?;
+            if x & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift.checked_mul(128).ok_or("Number in patch is too large")?;
+            value = value.checked_add(shift).ok_or("Number in patch is too large")?;
+        }
+    }
+
+    fn read_offset(&mut self) -> Result<i64, Box<dyn std::error::Error>> {
+        let value = self.read_number()?;
+        let offset = (value >> 1) as i64;
+        Ok(if value & 1 != 0 { -offset } else { offset })
+    }
+}
+
+/* CRC-32 as used by zip and BPS */
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut c = i as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+        }
+        *entry = c;
+    }
+
+    !data.iter().fold(!0u32, |c, &b| table[((c ^ b as u32) & 0xFF) as usize] ^ (c >> 8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROM_SIZE: usize = 0x300000;
+
+    /* A few scattered changes, a long run of the same byte and a long stretch of noise */
+    fn modified(source: &[u8]) -> Vec<u8> {
+        let mut target = source.to_vec();
+        target[0] = 1;
+        target[0x1234] = 0xFF;
+        target[0x10000..0x10100].iter_mut().for_each(|b| *b = 0xEA);
+        target[0x200000..0x220000].iter_mut().enumerate().for_each(|(i, b)| *b = (i * 7 + i / 251) as u8);
+        target[ROM_SIZE - 1] = 2;
+        target
+    }
+
+    fn round_trip(format: PatchFormat, source: &[u8], target: &[u8]) {
+        let patch = create(format, source, target).unwrap();
+        assert_eq!(apply(source, &patch).unwrap(), target);
+    }
+
+    #[test]
+    fn ips_round_trip() {
+        let source = vec![0u8; ROM_SIZE];
+        round_trip(PatchFormat::Ips, &source, &modified(&source));
+    }
+
+    #[test]
+    fn bps_round_trip() {
+        let source = vec![0u8; ROM_SIZE];
+        round_trip(PatchFormat::Bps, &source, &modified(&source));
+    }
+
+    #[test]
+    fn ips_record_at_eof_offset() {
+        let source = vec![0u8; IPS_EOF_OFFSET + 0x100];
+        let mut target = source.clone();
+        target[IPS_EOF_OFFSET] = 0x42;
+        target[IPS_EOF_OFFSET + 1] = 0x43;
+
+        let patch = create_ips(&source, &target).unwrap();
+        assert!(!patch[IPS_MAGIC.len()..patch.len() - IPS_FOOTER.len()].starts_with(IPS_FOOTER));
+        assert_eq!(&patch[IPS_MAGIC.len()..IPS_MAGIC.len() + 3], &[0x45, 0x4F, 0x45]);
+        assert_eq!(apply_ips(&source, &patch).unwrap(), target);
+    }
+
+    #[test]
+    fn shrinking_and_growing_targets() {
+        let source = modified(&vec![0u8; ROM_SIZE]);
+        let shrunk = source[..ROM_SIZE / 2].to_vec();
+        let mut grown = source.clone();
+        grown.extend((0..0x1000).map(|i| i as u8));
+        grown.extend(vec![0u8; 0x1000]);
+
+        for &format in &[PatchFormat::Ips, PatchFormat::Bps] {
+            round_trip(format, &source, &shrunk);
+            round_trip(format, &source, &grown);
+        }
+    }
+
+    #[test]
+    fn truncated_patches_fail() {
+        let source: Vec<u8> = (0..0x1000).map(|i| i as u8).collect();
+        let mut target = source.clone();
+        target[0x10..0x20].iter_mut().for_each(|b| *b = 0);
+        target[0x800] = 0xFF;
+
+        for &format in &[PatchFormat::Ips, PatchFormat::Bps] {
+            let patch = create(format, &source, &target).unwrap();
+            for length in 0..patch.len() {
+                assert!(apply(&source, &patch[..length]).is_err(), "{:?} patch cut to {} bytes applied", format, length);
+            }
+        }
+    }
+
+    #[test]
+    fn bps_rejects_wrong_source() {
+        let source = vec![0u8; 0x1000];
+        let mut target = source.clone();
+        target[0x100] = 1;
+        let patch = create_bps(&source, &target);
+
+        let mut other = source.clone();
+        other[0] = 1;
+        assert!(apply_bps(&other, &patch).is_err());
+    }
+
+    /* A patch with valid checksums around whatever commands are given */
+    fn bps_patch(source: &[u8], target_size: u64, commands: &[u8]) -> Vec<u8> {
+        let mut patch = BPS_MAGIC.to_vec();
+        write_number(&mut patch, source.len() as u64);
+        write_number(&mut patch, target_size);
+        write_number(&mut patch, 0);
+        patch.extend_from_slice(commands);
+        patch.extend_from_slice(&crc32(source).to_le_bytes());
+        patch.extend_from_slice(&0u32.to_le_bytes());
+        let patch_crc = crc32(&patch);
+        patch.extend_from_slice(&patch_crc.to_le_bytes());
+        patch
+    }
+
+    #[test]
+    fn bps_rejects_oversized_numbers() {
+        let source = vec![0u8; 0x10];
+        let patch = bps_patch(&source, 0x10, &[0x7F; 12]);
+        assert!(apply_bps(&source, &patch).is_err());
+    }
+
+    #[test]
+    fn bps_rejects_writes_past_the_target() {
+        let source = vec![0u8; 0x10];
+        let mut commands = Vec::new();
+        write_number(&mut commands, 1);
+        commands.push(0xAA);
+        /* Target copy of 0x100 bytes from offset 0, far past the target size of 0x10 */
+        write_number(&mut commands, (0xFF << 2) | 3);
+        write_number(&mut commands, 0);
+        let patch = bps_patch(&source, 0x10, &commands);
+        assert!(apply_bps(&source, &patch).is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use serde_derive::{Serialize, Deserialize};
+use crate::world::World;
+use crate::region::Region;
+use crate::node::Node;
+use crate::fill::{Placement, ItemPlacement};
+use crate::location::{Location, State};
+use crate::resources::Resources;
+use std::collections::HashSet;
+
+/* The order a placement can be played through in. Each sphere holds the locations that become reachable
+   with the items from all earlier spheres, the search stops once a sphere turns up nothing new. */
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Playthrough {
+    pub spheres: Vec<Vec<ItemPlacement>>,
+    pub events: Vec<String>,
+    pub total: usize,
+    pub is_beatable: bool
+}
+
+impl Playthrough {
+    pub fn new(world: &World, placement: &Placement) -> Result<Playthrough, Box<dyn std::error::Error>> {
+        if !placement.is_applied(world) {
+            bail!("Placement has doors or settings that are not set on the world, use Placement::apply first")
+        }
+        let (start_region, start_node) = match placement.get_start(world) {
+            Some(start) => start,
+            None => bail!(format!("Could not find start node {}:{} in world", placement.start_region, placement.start_node))
+        };
+        Ok(Playthrough::from_start(world, placement, start_region, start_node))
+    }
+
+    pub fn from_start(world: &World, placement: &Placement, start_region: &Region, start_node: &Node) -> Playthrough {
+        let techs = placement.get_techs();
+        let mut items: Vec<&str> = Vec::new();
+        let mut collected: HashSet<(i64, i64)> = HashSet::new();
+        let mut spheres = Vec::new();
+
+        loop {
+            let flags = world.get_flags(&items, &techs);
+            let mut state = State::with_capacity(start_region.id, start_node.id, Resources::from_inventory(&flags, &world.compiled.names));
+            let reachable = Location::available_from_state(&flags, world, start_region, start_node, &mut state).unwrap_or_default();
+
+            let sphere = reachable.iter()
+                .filter(|l| collected.insert((l.region.id, l.node.id)))
+                .filter_map(|l| placement.items.iter().find(|p| p.region == l.region.id && p.node == l.node.id))
+                .cloned()
+                .collect::<Vec<ItemPlacement>>();
+
+            if sphere.is_empty() {
+                let is_beatable = world.goal.is_met(world, &state);
+                let mut events = state.events.iter().map(|id| world.compiled.names.name(id).to_string()).collect::<Vec<String>>();
+                events.sort();
+                return Playthrough { spheres, events, total: placement.items.len(), is_beatable };
+            }
+
+            items.extend(sphere.iter().filter_map(|s| placement.get_item(s.region, s.node)));
+            spheres.push(sphere);
+        }
+    }
+
+    pub fn get_collected(&self) -> usize {
+        self.spheres.iter().map(|s| s.len()).sum()
+    }
+
+    /* Every location in the placement can be reached */
+    pub fn is_complete(&self) -> bool {
+        self.get_collected() == self.total
+    }
+
+    pub fn get_items(&self) -> Vec<&str> {
+        self.spheres.iter().flatten().map(|p| p.item.as_ref()).collect()
+    }
+}
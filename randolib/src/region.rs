@@ -2,6 +2,7 @@ use serde_derive::{Serialize, Deserialize};
 use crate::node::Node;
 use crate::link::Link;
 use crate::link::Obstacle;
+use crate::load_error::{LoadError, parse_json};
 use std::fs;
 
 
@@ -33,13 +34,17 @@ pub struct RegionFile {
 }
 
 impl RegionFile {
-    pub fn read(path: &str) -> Result<Vec<Region>, Box<dyn std::error::Error>> {
-        let data = fs::read_to_string(path)?;
-        let region_file: RegionFile = serde_json::from_str(&data)?;
+    pub fn read(path: &str) -> Result<Vec<Region>, LoadError> {
+        let data = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+        RegionFile::parse(&data, path)
+    }
+
+    pub fn parse(data: &str, path: &str) -> Result<Vec<Region>, LoadError> {
+        let region_file: RegionFile = parse_json(data, path)?;
         if let Some(regions) = region_file.rooms {
             Ok(regions)
         } else {
-            bail!(format!("Could not find rooms node in region file: {:?}", path))
+            Err(LoadError::missing_section(path, "rooms"))
         }
     }
 }
\ No newline at end of file
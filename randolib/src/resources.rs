@@ -0,0 +1,129 @@
+use serde_derive::{Serialize, Deserialize};
+use crate::node::Utility;
+use crate::inventory::Inventory;
+use crate::compiled::Names;
+
+pub const BASE_ENERGY: i64 = 99;
+pub const TANK_ENERGY: i64 = 100;
+pub const EXPANSION_AMMO: i64 = 5;
+
+/* Items that only matter through how many of them we hold */
+pub const RESOURCE_ITEMS: [&str; 5] = ["ETank", "ReserveTank", "Missile", "Super", "PowerBomb"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AmmoType {
+    Missile,
+    Super,
+    PowerBomb
+}
+
+impl AmmoType {
+    /* Ammo types as named in ammo and ammoDrain requirements */
+    pub fn from_name(name: &str) -> Option<AmmoType> {
+        match name {
+            "Missile" => Some(AmmoType::Missile),
+            "Super" => Some(AmmoType::Super),
+            "PowerBomb" => Some(AmmoType::PowerBomb),
+            _ => None
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Resources
+{
+    pub energy: i64,
+    pub reserves: i64,
+    pub missiles: i64,
+    pub supers: i64,
+    pub power_bombs: i64
+}
+
+impl Resources
+{
+    /* What Samus has before collecting anything */
+    pub fn base() -> Resources
+    {
+        Resources { energy: BASE_ENERGY, ..Resources::default() }
+    }
+
+    /* Maximum resources for an inventory, counting every tank and expansion held */
+    pub fn from_inventory(inventory: &Inventory, names: &Names) -> Resources
+    {
+        let count = |name: &str| names.id(name).map(|id| inventory.count(id) as i64).unwrap_or(0);
+        Resources
+        {
+            energy: BASE_ENERGY + count("ETank") * TANK_ENERGY,
+            reserves: count("ReserveTank") * TANK_ENERGY,
+            missiles: count("Missile") * EXPANSION_AMMO,
+            supers: count("Super") * EXPANSION_AMMO,
+            power_bombs: count("PowerBomb") * EXPANSION_AMMO
+        }
+    }
+
+    pub fn dominates(&self, other: &Resources) -> bool
+    {
+        self.energy >= other.energy && self.reserves >= other.reserves && self.missiles >= other.missiles && self.supers >= other.supers && self.power_bombs >= other.power_bombs
+    }
+
+    /* Rough measure used to pick the cheapest of several ways to satisfy a requirement */
+    pub fn total(&self) -> i64
+    {
+        self.energy + self.reserves + self.missiles + self.supers + self.power_bombs
+    }
+
+    /* Takes damage from regular energy first and then reserves, Samus has to survive with at least 1 energy */
+    pub fn spend_energy(&mut self, amount: i64) -> bool
+    {
+        if amount >= self.energy + self.reserves {
+            return false;
+        }
+
+        if amount < self.energy {
+            self.energy -= amount;
+        } else {
+            self.reserves -= amount - self.energy + 1;
+            self.energy = 1;
+        }
+        true
+    }
+
+    fn ammo(&mut self, ammo_type: AmmoType) -> &mut i64
+    {
+        match ammo_type {
+            AmmoType::Missile => &mut self.missiles,
+            AmmoType::Super => &mut self.supers,
+            AmmoType::PowerBomb => &mut self.power_bombs
+        }
+    }
+
+    pub fn spend_ammo(&mut self, ammo_type: AmmoType, count: i64) -> bool
+    {
+        let ammo = self.ammo(ammo_type);
+        if *ammo >= count {
+            *ammo -= count;
+            true
+        } else {
+            false
+        }
+    }
+
+    /* Ammo drains take what they can and never fail */
+    pub fn drain_ammo(&mut self, ammo_type: AmmoType, count: i64)
+    {
+        let ammo = self.ammo(ammo_type);
+        *ammo = (*ammo - count).max(0);
+    }
+
+    pub fn refill(&mut self, utility: &Utility, capacity: &Resources)
+    {
+        match utility {
+            Utility::Energy => self.energy = capacity.energy,
+            Utility::Reserve => self.reserves = capacity.reserves,
+            Utility::Missile => self.missiles = capacity.missiles,
+            Utility::Super => self.supers = capacity.supers,
+            Utility::PowerBomb => self.power_bombs = capacity.power_bombs,
+            Utility::Save | Utility::Map => {}
+        }
+    }
+}
@@ -0,0 +1,75 @@
+/* xoshiro256** seeded through splitmix64.
+   Seeds are shared between players, so the generator has to give the same sequence on every
+   platform and with every dependency version, which is why it lives here instead of using a crate. */
+#[derive(Debug, Clone)]
+pub struct Rng {
+    s: [u64; 4]
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        let mut sm = seed;
+        let mut s = [0u64; 4];
+        for v in s.iter_mut() {
+            sm = sm.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = sm;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            *v = z ^ (z >> 31);
+        }
+        Rng { s }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.s[1] << 17;
+        self.s[2] ^= self.s[0];
+        self.s[3] ^= self.s[1];
+        self.s[1] ^= self.s[2];
+        self.s[0] ^= self.s[3];
+        self.s[2] ^= t;
+        self.s[3] = self.s[3].rotate_left(45);
+        result
+    }
+
+    /* Uniform value in 0..n, n must be non-zero */
+    pub fn range(&mut self, n: usize) -> usize {
+        let n = n as u64;
+        let zone = u64::MAX - (u64::MAX % n);
+        loop {
+            let v = self.next_u64();
+            if v < zone {
+                return (v % n) as usize;
+            }
+        }
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.range(i + 1);
+            items.swap(i, j);
+        }
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.range(items.len())])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    /* Reference values for xoshiro256** seeded through splitmix64 with seed 0 */
+    #[test]
+    fn known_sequence() {
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.next_u64(), 0x99EC_5F36_CB75_F2B4);
+        assert_eq!(rng.next_u64(), 0xBF6E_1F78_4956_452A);
+        assert_eq!(rng.next_u64(), 0x1A5F_849D_4933_E6E0);
+    }
+}
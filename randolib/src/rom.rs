@@ -0,0 +1,258 @@
+use crate::world::World;
+use crate::fill::Placement;
+use crate::node::NodeSubType;
+use crate::patch::{self, PatchFormat};
+use crate::start::VANILLA_START;
+use std::fs;
+
+/* Copier headers put 512 bytes in front of the actual ROM data */
+const COPIER_HEADER: usize = 0x200;
+
+/* LoROM internal header */
+const TITLE_ADDRESS: usize = 0x7FC0;
+const TITLE: &[u8] = b"Super Metroid";
+const COMPLEMENT_ADDRESS: usize = 0x7FDC;
+const CHECKSUM_ADDRESS: usize = 0x7FDE;
+
+/* Item PLMs come in three blocks of the same order: visible items, items in chozo orbs and hidden items */
+const CHOZO_OFFSET: u16 = 0x54;
+const HIDDEN_OFFSET: u16 = 0xA8;
+const ITEM_PLMS: [(&str, u16); 21] = [
+    ("ETank", 0xEED7),
+    ("Missile", 0xEEDB),
+    ("Super", 0xEEDF),
+    ("PowerBomb", 0xEEE3),
+    ("Bombs", 0xEEE7),
+    ("Charge", 0xEEEB),
+    ("Ice", 0xEEEF),
+    ("HiJump", 0xEEF3),
+    ("SpeedBooster", 0xEEF7),
+    ("Wave", 0xEEFB),
+    ("Spazer", 0xEEFF),
+    ("SpringBall", 0xEF03),
+    ("Varia", 0xEF07),
+    ("Gravity", 0xEF0B),
+    ("XRayScope", 0xEF0F),
+    ("Plasma", 0xEF13),
+    ("Grapple", 0xEF17),
+    ("SpaceJump", 0xEF1B),
+    ("ScrewAttack", 0xEF1F),
+    ("Morph", 0xEF23),
+    ("ReserveTank", 0xEF27)
+];
+
+/* PLM id for an item placed in a location of the given kind */
+pub fn item_plm(item: &str, location_type: &NodeSubType) -> Option<u16> {
+    let (_, plm) = ITEM_PLMS.iter().find(|(name, _)| *name == item)?;
+    match location_type {
+        NodeSubType::Visible => Some(*plm),
+        NodeSubType::Chozo => Some(plm + CHOZO_OFFSET),
+        NodeSubType::Hidden => Some(plm + HIDDEN_OFFSET),
+        _ => None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rom {
+    pub data: Vec<u8>,
+    pub header: Option<Vec<u8>>
+}
+
+impl Rom {
+    pub fn from_bytes(mut data: Vec<u8>) -> Result<Rom, Box<dyn std::error::Error>> {
+        let header = if data.len() % 0x400 == COPIER_HEADER {
+            Some(data.drain(..COPIER_HEADER).collect())
+        } else {
+            None
+        };
+
+        if data.len() < TITLE_ADDRESS + TITLE.len() || &data[TITLE_ADDRESS..TITLE_ADDRESS + TITLE.len()] != TITLE {
+            bail!("Input file is not a Super Metroid ROM")
+        }
+
+        Ok(Rom { data, header })
+    }
+
+    pub fn read(path: &str) -> Result<Rom, Box<dyn std::error::Error>> {
+        Rom::from_bytes(fs::read(path)?)
+    }
+
+    /* The ROM as it should be written out, with the copier header put back if the input had one */
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match &self.header {
+            Some(header) => header.iter().chain(self.data.iter()).copied().collect(),
+            None => self.data.clone()
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    /* Patches are made against the ROM without its copier header so they work for both kinds of dumps */
+    pub fn create_patch(&self, original: &Rom, format: PatchFormat) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        patch::create(format, &original.data, &self.data)
+    }
+
+    pub fn write_patch(&self, path: &str, original: &Rom, format: PatchFormat) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, self.create_patch(original, format)?)?;
+        Ok(())
+    }
+
+    pub fn apply_patch(&mut self, patch: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.data = patch::apply(&self.data, patch)?;
+        Ok(())
+    }
+
+    pub fn write_u16(&mut self, address: usize, value: u16) -> Result<(), Box<dyn std::error::Error>> {
+        if address + 2 > self.data.len() {
+            bail!(format!("Address {:#X} is outside of the ROM", address))
+        }
+        self.data[address..address + 2].copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    /* Writes the PLM for every placed item over the vanilla one at the node address and fixes up the checksum */
+    pub fn apply_placement(&mut self, world: &World, placement: &Placement) -> Result<(), Box<dyn std::error::Error>> {
+        if placement.connections.is_some() {
+            bail!("Shuffled doors can't be written to the ROM yet")
+        }
+        if (placement.start_region, placement.start_node) != VANILLA_START {
+            bail!("Starting anywhere but the Landing Site ship can't be written to the ROM yet")
+        }
+        for item in &placement.items {
+            let node = match world.get_node(item.region, item.node) {
+                Some((_, node)) => node,
+                None => bail!(format!("Could not find item location {}:{} in world", item.region, item.node))
+            };
+
+            let address = match &node.nodeAddress {
+                Some(address) => usize::from_str_radix(address.trim_start_matches("0x"), 16)?,
+                None => bail!(format!("Item location {} has no address", node.name))
+            };
+
+            let plm = match node.nodeSubType.as_ref().and_then(|t| item_plm(&item.item, t)) {
+                Some(plm) => plm,
+                None => bail!(format!("No PLM for {} at {}", item.item, node.name))
+            };
+
+            self.write_u16(address, plm)?;
+        }
+
+        self.update_checksum();
+        Ok(())
+    }
+
+    /* Sums every byte, mirroring the part past the largest power of two like the hardware does for
+       odd sized ROMs. The complement and checksum are reset first so they add up to 0x1FE as they will once written. */
+    pub fn update_checksum(&mut self) {
+        let len = self.data.len();
+        if len < CHECKSUM_ADDRESS + 2 {
+            return;
+        }
+
+        self.data[COMPLEMENT_ADDRESS..COMPLEMENT_ADDRESS + 2].copy_from_slice(&[0xFF, 0xFF]);
+        self.data[CHECKSUM_ADDRESS..CHECKSUM_ADDRESS + 2].copy_from_slice(&[0x00, 0x00]);
+
+        let sum = |bytes: &[u8]| bytes.iter().fold(0u32, |a, &b| a.wrapping_add(b as u32));
+        let base = if len.is_power_of_two() { len } else { len.next_power_of_two() / 2 };
+        let mut checksum = sum(&self.data[..base]);
+        if base < len {
+            let rest = &self.data[base..];
+            checksum = checksum.wrapping_add(sum(rest).wrapping_mul((base / rest.len()) as u32));
+        }
+
+        let checksum = checksum as u16;
+        self.data[COMPLEMENT_ADDRESS..COMPLEMENT_ADDRESS + 2].copy_from_slice(&(!checksum).to_le_bytes());
+        self.data[CHECKSUM_ADDRESS..CHECKSUM_ADDRESS + 2].copy_from_slice(&checksum.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* The smallest buffer that passes as a ROM, with the title in the internal header */
+    fn synthetic(len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        data[TITLE_ADDRESS..TITLE_ADDRESS + TITLE.len()].copy_from_slice(TITLE);
+        data
+    }
+
+    fn read_u16(rom: &Rom, address: usize) -> u16 {
+        u16::from_le_bytes([rom.data[address], rom.data[address + 1]])
+    }
+
+    #[test]
+    fn strips_copier_header() {
+        let mut bytes = vec![0xAAu8; COPIER_HEADER];
+        bytes.extend(synthetic(0x8000));
+
+        let rom = Rom::from_bytes(bytes.clone()).unwrap();
+        assert_eq!(rom.header.as_ref().map(|h| h.len()), Some(COPIER_HEADER));
+        assert_eq!(rom.data.len(), 0x8000);
+        assert_eq!(rom.to_bytes(), bytes);
+
+        let rom = Rom::from_bytes(synthetic(0x8000)).unwrap();
+        assert!(rom.header.is_none());
+        assert_eq!(rom.to_bytes().len(), 0x8000);
+    }
+
+    #[test]
+    fn rejects_other_roms() {
+        assert!(Rom::from_bytes(vec![0u8; 0x8000]).is_err());
+        assert!(Rom::from_bytes(vec![0u8; 0x100]).is_err());
+    }
+
+    #[test]
+    fn item_plm_offsets() {
+        assert_eq!(item_plm("Missile", &NodeSubType::Visible), Some(0xEEDB));
+        assert_eq!(item_plm("Missile", &NodeSubType::Chozo), Some(0xEEDB + 0x54));
+        assert_eq!(item_plm("Missile", &NodeSubType::Hidden), Some(0xEEDB + 0xA8));
+        assert_eq!(item_plm("ReserveTank", &NodeSubType::Hidden), Some(0xEFCF));
+        assert_eq!(item_plm("Missile", &NodeSubType::Boss), None);
+        assert_eq!(item_plm("Nothing", &NodeSubType::Visible), None);
+    }
+
+    #[test]
+    fn only_vanilla_starts_are_written() {
+        let world = crate::test_world::world();
+        let (region, node) = world.get_node(2, 1).unwrap();
+        let placement = crate::fill::fill(&world, &world.get_item_pool(), &[], region, node, 1, crate::fill::FillAlgorithm::Assumed).unwrap();
+
+        let mut rom = Rom::from_bytes(synthetic(0x8000)).unwrap();
+        let error = rom.apply_placement(&world, &placement).unwrap_err();
+        assert!(error.to_string().contains("Landing Site ship"));
+    }
+
+    #[test]
+    fn checksum_of_power_of_two_rom() {
+        let mut rom = Rom::from_bytes(synthetic(0x8000)).unwrap();
+        rom.update_checksum();
+
+        let title = TITLE.iter().map(|&b| b as u16).sum::<u16>();
+        assert_eq!(read_u16(&rom, CHECKSUM_ADDRESS), title + 0x1FE);
+        assert_eq!(read_u16(&rom, COMPLEMENT_ADDRESS), !(title + 0x1FE));
+    }
+
+    #[test]
+    fn checksum_mirrors_the_last_megabyte_of_a_3mb_rom() {
+        let mut data = synthetic(0x300000);
+        data[0x1FFFFF] = 1;
+        data[0x200000] = 3;
+        data[0x2FFFFF] = 5;
+        let mut rom = Rom::from_bytes(data).unwrap();
+        rom.update_checksum();
+
+        /* The 1MB past the first 2MB is summed twice to fill out 4MB */
+        let title = TITLE.iter().map(|&b| b as u16).sum::<u16>();
+        let expected = title + 0x1FE + 1 + 2 * (3 + 5);
+        assert_eq!(read_u16(&rom, CHECKSUM_ADDRESS), expected);
+        assert_eq!(read_u16(&rom, COMPLEMENT_ADDRESS), !expected);
+
+        /* Writing it back doesn't change the result */
+        rom.update_checksum();
+        assert_eq!(read_u16(&rom, CHECKSUM_ADDRESS), expected);
+    }
+}
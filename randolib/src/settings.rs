@@ -0,0 +1,126 @@
+use serde_derive::{Serialize, Deserialize};
+use crate::damage::Leniency;
+use crate::world::World;
+use std::collections::BTreeMap;
+use std::fs;
+
+const DEFAULT_PRESETS: &str = include_str!("presets.json");
+
+/* A named difficulty. Presets can extend another preset, adding to its techs and strat toggles,
+   and use its leniency and notable strat default unless they set their own. */
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Preset {
+    pub name: String,
+    pub extends: Option<String>,
+    #[serde(default)]
+    pub techs: Vec<String>,
+    pub leniency: Option<Leniency>,
+    pub notable_strats: Option<bool>,
+    #[serde(default)]
+    pub strat_toggles: BTreeMap<String, BTreeMap<String, bool>>
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PresetFile {
+    pub presets: Vec<Preset>
+}
+
+impl PresetFile {
+    pub fn read(path: &str) -> Result<PresetFile, Box<dyn std::error::Error>> {
+        let data = fs::read_to_string(path)?;
+        PresetFile::parse(&data)
+    }
+
+    pub fn parse(data: &str) -> Result<PresetFile, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_str(data)?)
+    }
+
+    /* The presets that ship with the library: casual, normal, hard and expert */
+    pub fn default_presets() -> PresetFile {
+        PresetFile::parse(DEFAULT_PRESETS).unwrap()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.presets.iter().map(|p| p.name.as_ref()).collect()
+    }
+
+    /* Looks up a preset and folds in everything it extends */
+    pub fn get(&self, name: &str) -> Result<Settings, Box<dyn std::error::Error>> {
+        let mut chain: Vec<&Preset> = Vec::new();
+        let mut next = Some(name);
+
+        while let Some(name) = next {
+            if chain.iter().any(|p| p.name == name) {
+                bail!(format!("Preset {} extends itself", name))
+            }
+            match self.presets.iter().find(|p| p.name == name) {
+                Some(preset) => {
+                    chain.push(preset);
+                    next = preset.extends.as_deref();
+                },
+                None => bail!(format!("Could not find preset {}", name))
+            }
+        }
+
+        let mut techs: Vec<String> = Vec::new();
+        for tech in chain.iter().rev().flat_map(|p| &p.techs) {
+            if !techs.contains(tech) {
+                techs.push(tech.to_string());
+            }
+        }
+
+        let mut strat_toggles: BTreeMap<String, BTreeMap<String, bool>> = BTreeMap::new();
+        for (room, toggles) in chain.iter().rev().flat_map(|p| &p.strat_toggles) {
+            strat_toggles.entry(room.to_string()).or_default().extend(toggles.iter().map(|(s, &t)| (s.to_string(), t)));
+        }
+
+        Ok(Settings {
+            preset: name.to_string(),
+            techs,
+            leniency: chain.iter().find_map(|p| p.leniency).unwrap_or_default(),
+            notable_strats: chain.iter().find_map(|p| p.notable_strats).unwrap_or(true),
+            strat_toggles
+        })
+    }
+}
+
+/* Resolved settings for a seed */
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Settings {
+    pub preset: String,
+    pub techs: Vec<String>,
+    pub leniency: Leniency,
+    pub notable_strats: bool,
+    pub strat_toggles: BTreeMap<String, BTreeMap<String, bool>>
+}
+
+impl Settings {
+    pub fn get_techs(&self) -> Vec<&str> {
+        self.techs.iter().map(|t| t.as_ref()).collect()
+    }
+
+    /* Sets the leniency and strat toggles in the world logic, the techs are passed along to World::get_flags */
+    pub fn apply(&self, world: &mut World) {
+        world.logic.leniency = self.leniency;
+        world.logic.notable_strats = self.notable_strats;
+        world.logic.strat_toggles = self.strat_toggles.clone();
+    }
+
+    pub fn is_applied(&self, world: &World) -> bool {
+        world.logic.leniency == self.leniency && world.logic.notable_strats == self.notable_strats && world.logic.strat_toggles == self.strat_toggles
+    }
+
+    /* Toggles for strats the world data doesn't have, given as (room, strat) */
+    pub fn unknown_strats(&self, world: &World) -> Vec<(&str, &str)> {
+        let notable = world.get_notable_strats();
+        self.strat_toggles.iter()
+            .flat_map(|(room, toggles)| toggles.keys().map(move |strat| (room.as_ref(), strat.as_ref())))
+            .filter(|s| !notable.contains(s))
+            .collect()
+    }
+
+    /* Techs in the settings that the world data doesn't know about, usually a typo in a preset */
+    pub fn unknown_techs(&self, world: &World) -> Vec<&str> {
+        self.get_techs().into_iter().filter(|t| !world.techs.iter().any(|w| w.name == *t)).collect()
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+/* Where the world data files come from. Paths are relative to the data root and use / as separator,
+   like "region/crateria/west.json" or "tech.json". */
+pub trait WorldSource {
+    fn files(&self) -> Result<Vec<String>, Box<dyn std::error::Error>>;
+    fn read(&self, path: &str) -> Result<String, Box<dyn std::error::Error>>;
+}
+
+/* An sm-json-data checkout on disk */
+pub struct DirectorySource {
+    root: PathBuf
+}
+
+impl DirectorySource {
+    pub fn new(root: &str) -> DirectorySource {
+        DirectorySource { root: PathBuf::from(root) }
+    }
+}
+
+impl WorldSource for DirectorySource {
+    fn files(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut files = Vec::new();
+        for entry in WalkDir::new(&self.root).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+            let path = entry.path().strip_prefix(&self.root)?;
+            files.push(path.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"));
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn read(&self, path: &str) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(fs::read_to_string(self.root.join(path))?)
+    }
+}
+
+/* Files already in memory, keyed by path */
+impl WorldSource for BTreeMap<String, String> {
+    fn files(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(self.keys().cloned().collect())
+    }
+
+    fn read(&self, path: &str) -> Result<String, Box<dyn std::error::Error>> {
+        match self.get(path) {
+            Some(data) => Ok(data.to_string()),
+            None => bail!("Not found in the world files")
+        }
+    }
+}
+
+/* Archives of the repository usually put everything in a top level folder like "sm-json-data-master/",
+   so that is dropped when no data file sits at the root of the archive */
+#[cfg(any(feature = "zip", feature = "tar"))]
+fn strip_root_folder(files: BTreeMap<String, String>) -> BTreeMap<String, String> {
+    if files.contains_key("tech.json") {
+        return files;
+    }
+
+    let root = match files.keys().find(|p| p.ends_with("/tech.json")) {
+        Some(path) => path.trim_end_matches("tech.json").to_string(),
+        None => return files
+    };
+
+    files.into_iter().filter_map(|(path, data)| path.strip_prefix(&root).map(|p| (p.to_string(), data))).collect()
+}
+
+/* Reads the JSON files out of a zip archive of the data */
+#[cfg(feature = "zip")]
+pub fn from_zip(data: &[u8]) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+    use std::io::Read;
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data))?;
+    let mut files = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.is_file() && file.name().ends_with(".json") {
+            let mut contents = String::new();
+            file.read_to_string(&mut contents)?;
+            files.insert(file.name().to_string(), contents);
+        }
+    }
+    Ok(strip_root_folder(files))
+}
+
+/* Reads the JSON files out of an uncompressed tar archive of the data */
+#[cfg(feature = "tar")]
+pub fn from_tar(data: &[u8]) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error>> {
+    use std::io::Read;
+
+    let mut archive = tar::Archive::new(data);
+    let mut files = BTreeMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().trim_start_matches("./").to_string();
+        if entry.header().entry_type().is_file() && path.ends_with(".json") {
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents)?;
+            files.insert(path, contents);
+        }
+    }
+    Ok(strip_root_folder(files))
+}
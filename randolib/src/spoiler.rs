@@ -0,0 +1,117 @@
+use serde_derive::{Serialize, Deserialize};
+use crate::world::World;
+use crate::fill::{Placement, FillAlgorithm};
+use crate::playthrough::Playthrough;
+use crate::damage::Leniency;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SpoilerSettings {
+    pub algorithm: FillAlgorithm,
+    pub start: String,
+    pub techs: Vec<String>,
+    pub leniency: Leniency,
+    pub notable_strats: bool,
+    pub strat_toggles: BTreeMap<String, BTreeMap<String, bool>>
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SpoilerLocation {
+    pub room: String,
+    pub location: String,
+    pub item: String
+}
+
+/* Locations are grouped by area and then subarea, spheres list what becomes reachable with the items from earlier spheres */
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Spoiler {
+    pub seed: u64,
+    pub settings: SpoilerSettings,
+    pub locations: BTreeMap<String, BTreeMap<String, Vec<SpoilerLocation>>>,
+    pub spheres: Vec<Vec<SpoilerLocation>>
+}
+
+impl Spoiler {
+    pub fn new(world: &World, placement: &Placement) -> Result<Spoiler, Box<dyn std::error::Error>> {
+        let start = match placement.get_start(world) {
+            Some((_, node)) => node.name.to_string(),
+            None => bail!(format!("Could not find start node {}:{} in world", placement.start_region, placement.start_node))
+        };
+
+        let mut locations: BTreeMap<String, BTreeMap<String, Vec<SpoilerLocation>>> = BTreeMap::new();
+        for item in &placement.items {
+            if let Some(region) = world.get_region(item.region) {
+                locations.entry(region.area.to_string()).or_default().entry(region.subarea.to_string()).or_default().push(SpoilerLocation {
+                    room: region.name.to_string(),
+                    location: item.location.to_string(),
+                    item: item.item.to_string()
+                });
+            }
+        }
+
+        Ok(Spoiler {
+            seed: placement.seed,
+            settings: SpoilerSettings {
+                algorithm: placement.algorithm,
+                start,
+                techs: placement.techs.clone(),
+                leniency: world.logic.leniency,
+                notable_strats: world.logic.notable_strats,
+                strat_toggles: world.logic.strat_toggles.clone()
+            },
+            locations,
+            spheres: Spoiler::get_spheres(world, placement)?
+        })
+    }
+
+    fn get_spheres(world: &World, placement: &Placement) -> Result<Vec<Vec<SpoilerLocation>>, Box<dyn std::error::Error>> {
+        let playthrough = Playthrough::new(world, placement)?;
+        Ok(playthrough.spheres.iter().map(|sphere| sphere.iter().map(|p| SpoilerLocation {
+            room: world.get_region(p.region).map(|r| r.name.to_string()).unwrap_or_default(),
+            location: p.location.to_string(),
+            item: p.item.to_string()
+        }).collect()).collect())
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        writeln!(text, "Seed: {}", self.seed).unwrap();
+        writeln!(text, "Algorithm: {:?}", self.settings.algorithm).unwrap();
+        writeln!(text, "Start: {}", self.settings.start).unwrap();
+        writeln!(text, "Techs: {}", self.settings.techs.join(", ")).unwrap();
+        let leniency = &self.settings.leniency;
+        writeln!(text, "Leniency: heat {}, lava {}, acid {}, electricity {}, spikes {}, hibashi {}, enemy damage {}",
+            leniency.heat, leniency.lava, leniency.acid, leniency.electricity, leniency.spikes, leniency.hibashi, leniency.enemy_damage).unwrap();
+        writeln!(text, "Notable strats: {}", if self.settings.notable_strats { "on" } else { "off" }).unwrap();
+        for (room, toggles) in &self.settings.strat_toggles {
+            for (strat, enabled) in toggles {
+                writeln!(text, "  {}: {} {}", room, strat, if *enabled { "on" } else { "off" }).unwrap();
+            }
+        }
+
+        writeln!(text, "\nItem locations").unwrap();
+        for (area, subareas) in &self.locations {
+            for (subarea, locations) in subareas {
+                writeln!(text, "\n  {} - {}", area, subarea).unwrap();
+                for location in locations {
+                    writeln!(text, "    {} ({}): {}", location.location, location.room, location.item).unwrap();
+                }
+            }
+        }
+
+        writeln!(text, "\nPlaythrough").unwrap();
+        for (i, sphere) in self.spheres.iter().enumerate() {
+            writeln!(text, "\n  Sphere {}", i + 1).unwrap();
+            for location in sphere {
+                writeln!(text, "    {} ({}): {}", location.location, location.room, location.item).unwrap();
+            }
+        }
+
+        text
+    }
+}
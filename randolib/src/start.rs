@@ -0,0 +1,53 @@
+use crate::world::World;
+use crate::region::Region;
+use crate::node::{Node, Utility};
+use crate::location::Location;
+use crate::rng::Rng;
+
+/* Room and node ids of the ship in Landing Site, where the game starts */
+pub const VANILLA_START: (i64, i64) = (8, 5);
+
+pub fn vanilla_start(world: &World) -> Result<(&Region, &Node), Box<dyn std::error::Error>> {
+    match world.get_node(VANILLA_START.0, VANILLA_START.1) {
+        Some(start) => Ok(start),
+        None => bail!("Could not find the Landing Site ship in the world")
+    }
+}
+
+/* Every save station in the world, these are the default start candidates */
+pub fn save_stations(world: &World) -> Vec<(&Region, &Node)> {
+    world.regions.iter()
+        .flat_map(|r| r.nodes.iter().map(move |n| (r, n)))
+        .filter(|(_, n)| n.utility.iter().flatten().any(|u| u == &Utility::Save))
+        .collect()
+}
+
+/* Picks a random start out of the named candidates, or the save stations if none are given.
+   A start is only used if at least `min_locations` item locations can be reached from it without any items. */
+pub fn random_start<'a>(world: &'a World, candidates: &[&str], techs: &[&str], min_locations: usize, seed: u64) -> Result<(&'a Region, &'a Node), Box<dyn std::error::Error>> {
+    let mut starts = if candidates.is_empty() {
+        save_stations(world)
+    } else {
+        let mut starts = Vec::new();
+        for &name in candidates {
+            match world.find_node(name) {
+                Some(start) => starts.push(start),
+                None => bail!(format!("Could not find start location {}", name))
+            }
+        }
+        starts
+    };
+
+    let mut rng = Rng::new(seed);
+    rng.shuffle(&mut starts);
+
+    let flags = world.get_flags(&[], techs);
+    for (region, node) in starts {
+        let locations = Location::available(&flags, world, region, node).unwrap_or_default();
+        if locations.len() >= min_locations {
+            return Ok((region, node));
+        }
+    }
+
+    bail!(format!("No start location has at least {} reachable item locations", min_locations))
+}
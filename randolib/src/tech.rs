@@ -1,5 +1,6 @@
 use serde_derive::{Serialize,Deserialize};
 use crate::helper::Helper;
+use crate::load_error::{LoadError, parse_json};
 use std::fs;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -10,13 +11,17 @@ pub struct TechFile {
 }
 
 impl TechFile {
-    pub fn read(path: &str) -> Result<Vec<Helper>, Box<dyn std::error::Error>> {
-        let data = fs::read_to_string(path)?;
-        let tech_file: TechFile = serde_json::from_str(&data)?;
+    pub fn read(path: &str) -> Result<Vec<Helper>, LoadError> {
+        let data = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+        TechFile::parse(&data, path)
+    }
+
+    pub fn parse(data: &str, path: &str) -> Result<Vec<Helper>, LoadError> {
+        let tech_file: TechFile = parse_json(data, path)?;
         if let Some(techs) = tech_file.techs {
             Ok(techs)
         } else {
-            bail!(format!("Could not find techs node in tech file: {:?}", path))
+            Err(LoadError::missing_section(path, "techs"))
         }
     }
 }
\ No newline at end of file
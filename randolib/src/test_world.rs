@@ -0,0 +1,85 @@
+use crate::world::World;
+use std::collections::BTreeMap;
+
+/* A tiny world for the tests, laid out like sm-json-data. Landing Site and its ship use the real ids (8 and 5).
+   Morph is free, the Landing Site ledge and the Missile alcove need Morph and Mother Brain needs Missile. */
+const LANDING_SITE: &str = r#"{"$schema": "", "rooms": [
+ {"id": 8, "name": "Landing Site", "area": "Crateria", "subarea": "Central",
+  "nodes": [
+   {"id": 1, "name": "Landing Site Door", "nodeType": "door", "nodeSubType": "blue"},
+   {"id": 2, "name": "Landing Site Ledge", "nodeType": "item", "nodeSubType": "hidden", "nodeItem": "ETank"},
+   {"id": 5, "name": "Ship", "nodeType": "junction", "nodeSubType": "junction", "utility": ["save", "energy", "missile"]}
+  ],
+  "links": [
+   {"from": 1, "to": [{"id": 5, "strats": [{"name": "Base", "notable": false, "requires": []}]}]},
+   {"from": 2, "to": [{"id": 5, "strats": [{"name": "Base", "notable": false, "requires": []}]}]},
+   {"from": 5, "to": [
+    {"id": 1, "strats": [{"name": "Base", "notable": false, "requires": []}]},
+    {"id": 2, "strats": [{"name": "Base", "notable": false, "requires": ["Morph"]}]}
+   ]}
+  ]}
+]}"#;
+
+const MORPH_ROOM: &str = r#"{"$schema": "", "rooms": [
+ {"id": 2, "name": "Morph Ball Room", "area": "Brinstar", "subarea": "Blue",
+  "nodes": [
+   {"id": 1, "name": "Morph Ball Room Left Door", "nodeType": "door", "nodeSubType": "blue"},
+   {"id": 2, "name": "Morphing Ball", "nodeType": "item", "nodeSubType": "visible", "nodeItem": "Morph"},
+   {"id": 3, "name": "Missile Alcove", "nodeType": "item", "nodeSubType": "chozo", "nodeItem": "Missile"},
+   {"id": 4, "name": "Mother Brain", "nodeType": "event", "nodeSubType": "boss", "yields": ["f_DefeatedMotherBrain"]}
+  ],
+  "links": [
+   {"from": 1, "to": [
+    {"id": 2, "strats": [{"name": "Base", "notable": false, "requires": []}]},
+    {"id": 3, "strats": [{"name": "Base", "notable": false, "requires": ["Morph"]}]},
+    {"id": 4, "strats": [{"name": "Base", "notable": false, "requires": ["Missile"]}]}
+   ]},
+   {"from": 2, "to": [{"id": 1, "strats": [{"name": "Base", "notable": false, "requires": []}]}]},
+   {"from": 3, "to": [{"id": 1, "strats": [{"name": "Base", "notable": false, "requires": []}]}]},
+   {"from": 4, "to": [{"id": 1, "strats": [{"name": "Base", "notable": false, "requires": []}]}]}
+  ]}
+]}"#;
+
+const CONNECTIONS: &str = r#"{"$schema": "", "connections": [
+ {"connectionType": "Bidirectional", "description": "Landing Site to Morph Ball Room", "nodes": [
+  {"area": "Crateria", "subarea": "Central", "roomid": 8, "nodeid": 1, "position": "right"},
+  {"area": "Brinstar", "subarea": "Blue", "roomid": 2, "nodeid": 1, "position": "left"}
+ ]}
+]}"#;
+
+/* Beams can't hurt the boss, it takes 8 Missiles or 2 Supers since Supers do double damage to it */
+const ENEMIES: &str = r#"{"$schema": "", "enemies": [
+ {"id": 1, "name": "Boss", "hp": 800, "attacks": [{"name": "contact", "baseDamage": 60}],
+  "damageMultipliers": [{"weapon": "Super", "value": 2}], "invul": ["Beam"]},
+ {"id": 2, "name": "Zoomer", "hp": 30, "attacks": [{"name": "contact", "baseDamage": 10}]}
+]}"#;
+const WEAPONS: &str = r#"{"$schema": "", "weapons": [
+ {"id": 1, "name": "PowerBeam", "damage": 20, "useRequires": [], "situational": false, "hitsGroup": false, "categories": ["Beam"]},
+ {"id": 2, "name": "Missile", "damage": 100, "useRequires": ["Missile"], "shotRequires": [{"ammo": {"type": "Missile", "count": 1}}], "situational": false, "hitsGroup": false, "categories": ["Missile"]},
+ {"id": 3, "name": "Super", "damage": 300, "useRequires": ["Super"], "shotRequires": [{"ammo": {"type": "Super", "count": 1}}], "situational": false, "hitsGroup": false, "categories": ["Super"]}
+]}"#;
+const HELPERS: &str = r#"{"$schema": "", "helpers": []}"#;
+const TECHS: &str = r#"{"$schema": "", "techs": [{"name": "canWalljump", "requires": []}]}"#;
+
+pub fn files() -> BTreeMap<String, String> {
+    [
+        ("region/crateria/landing.json", LANDING_SITE),
+        ("region/brinstar/morph.json", MORPH_ROOM),
+        ("connection/main.json", CONNECTIONS),
+        ("enemies/main.json", ENEMIES),
+        ("weapons/main.json", WEAPONS),
+        ("helpers.json", HELPERS),
+        ("tech.json", TECHS)
+    ].iter().map(|(path, data)| (path.to_string(), data.to_string())).collect()
+}
+
+pub fn world() -> World {
+    World::load_from_files("test", &files()).unwrap()
+}
+
+/* The test world with extra data files added or replaced */
+pub fn world_with(extra: &[(&str, &str)]) -> World {
+    let mut files = files();
+    files.extend(extra.iter().map(|(path, data)| (path.to_string(), data.to_string())));
+    World::load_from_files("test", &files).unwrap()
+}
@@ -0,0 +1,237 @@
+use crate::world::World;
+use crate::region::Region;
+use crate::link::Strat;
+use crate::requirement::Requirement;
+use crate::resources::{AmmoType, RESOURCE_ITEMS};
+use std::collections::HashSet;
+use std::fmt;
+
+/* A problem in the world data. The location says where in the room it is, like "link 1 -> 3, strat \"Base\"" */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub room: Option<String>,
+    pub location: String,
+    pub message: String
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.room {
+            Some(room) => write!(f, "{}: {}: {}", room, self.location, self.message),
+            None => write!(f, "{}: {}", self.location, self.message)
+        }
+    }
+}
+
+/* Checks that everything the data refers to actually exists. A typo in a name or id doesn't fail to load,
+   it just makes the requirement impossible, so this is the only place it shows up. */
+pub fn validate(world: &World) -> Vec<Diagnostic> {
+    let mut validator = Validator::new(world);
+
+    for region in &world.regions {
+        validator.check_region(region);
+    }
+
+    for connection in &world.connections {
+        for node in &connection.nodes {
+            if world.get_node(node.roomid, node.nodeid).is_none() {
+                let location = format!("connection {}", connection.description.as_deref().unwrap_or("without description"));
+                validator.report(None, &location, format!("points at missing node {}:{}", node.roomid, node.nodeid));
+            }
+        }
+    }
+
+    for helper in &world.helpers {
+        if let Some(requires) = &helper.requires {
+            validator.check_requirement(requires, None, &format!("helper {}", helper.name));
+        }
+    }
+
+    for tech in &world.techs {
+        if let Some(requires) = &tech.requires {
+            validator.check_requirement(requires, None, &format!("tech {}", tech.name));
+        }
+    }
+
+    for weapon in &world.weapons {
+        validator.check_requirement(&weapon.useRequires, None, &format!("weapon {}", weapon.name));
+        if let Some(requires) = &weapon.shotRequires {
+            validator.check_requirement(requires, None, &format!("weapon {}", weapon.name));
+        }
+    }
+
+    validator.diagnostics
+}
+
+struct Validator<'a> {
+    world: &'a World,
+    names: HashSet<&'a str>,
+    diagnostics: Vec<Diagnostic>
+}
+
+impl<'a> Validator<'a> {
+    /* Anything a Req can name: items, helpers, techs and the events and flags yielded somewhere in the data */
+    fn new(world: &'a World) -> Validator<'a> {
+        let mut names: HashSet<&str> = world.get_item_pool().into_iter().collect();
+        names.extend(RESOURCE_ITEMS.iter());
+        names.extend(world.helpers.iter().chain(world.techs.iter()).map(|h| h.name.as_str()));
+        for region in &world.regions {
+            names.extend(region.nodes.iter().flat_map(|n| n.yields.iter().flatten()).map(|y| y.as_str()));
+            names.extend(region.links.iter().flat_map(|l| &l.to).flat_map(|l| l.yields.iter().flatten()).map(|y| y.as_str()));
+        }
+
+        Validator { world, names, diagnostics: Vec::new() }
+    }
+
+    fn report(&mut self, region: Option<&Region>, location: &str, message: String) {
+        self.diagnostics.push(Diagnostic { room: region.map(|r| r.name.to_string()), location: location.to_string(), message });
+    }
+
+    fn check_node_id(&mut self, region: Option<&Region>, location: &str, what: &str, id: i64) {
+        if let Some(region) = region {
+            if !region.nodes.iter().any(|n| n.id == id) {
+                self.report(Some(region), location, format!("{} refers to missing node {}", what, id));
+            }
+        }
+    }
+
+    fn check_region(&mut self, region: &Region) {
+        for node in &region.nodes {
+            let location = format!("node {}", node.id);
+            for requires in node.interactionRequires.iter().chain(node.view.iter()) {
+                self.check_requirement(requires, Some(region), &location);
+            }
+            for lock in node.locks.iter().flatten() {
+                let location = format!("node {}, lock {}", node.id, lock.name.as_deref().unwrap_or("without name"));
+                if let Some(requires) = &lock.lock {
+                    self.check_requirement(requires, Some(region), &location);
+                }
+                for strat in lock.unlockStrats.iter().flatten().chain(lock.bypassStrats.iter().flatten()) {
+                    self.check_strat(strat, region, &location);
+                }
+            }
+            for strat in node.get_runways().flat_map(|r| r.strats.iter().flatten()) {
+                self.check_strat(strat, region, &format!("node {}, runway", node.id));
+            }
+            for strat in node.sparking.iter().flat_map(|s| s.canLeaveCharged.iter().flatten()).flat_map(|c| c.strats.iter().flatten()) {
+                self.check_strat(strat, region, &format!("node {}, canLeaveCharged", node.id));
+            }
+        }
+
+        for link in &region.links {
+            self.check_node_id(Some(region), &format!("link from {}", link.from), "link", link.from);
+            for to in &link.to {
+                let location = format!("link {} -> {}", link.from, to.id);
+                self.check_node_id(Some(region), &location, "link", to.id);
+                for requires in to.requires.iter().chain(to.unlock.iter()) {
+                    self.check_requirement(requires, Some(region), &location);
+                }
+                for strat in to.strats.iter().flatten() {
+                    self.check_strat(strat, region, &location);
+                }
+            }
+        }
+
+        for obstacle in region.obstacles.iter().flatten() {
+            let location = format!("obstacle {}", obstacle.id.as_deref().unwrap_or("without id"));
+            for requires in obstacle.requires.iter().chain(obstacle.bypass.iter()) {
+                self.check_requirement(requires, Some(region), &location);
+            }
+        }
+    }
+
+    fn check_strat(&mut self, strat: &Strat, region: &Region, location: &str) {
+        let location = format!("{}, strat \"{}\"", location, strat.name);
+        if let Some(requires) = &strat.requires {
+            self.check_requirement(requires, Some(region), &location);
+        }
+
+        for obstacle in strat.obstacles.iter().flatten() {
+            if let Some(id) = &obstacle.id {
+                if !region.obstacles.iter().flatten().any(|o| o.id.as_ref() == Some(id)) {
+                    self.report(Some(region), &location, format!("obstacle {} is not in the room obstacles", id));
+                }
+            }
+            for requires in obstacle.requires.iter().chain(obstacle.bypass.iter()) {
+                self.check_requirement(requires, Some(region), &location);
+            }
+        }
+    }
+
+    /* Requirements outside of a room, like helpers, have no nodes to check against */
+    fn check_requirement(&mut self, requirement: &Requirement, region: Option<&Region>, location: &str) {
+        match requirement {
+            Requirement::Or { or: reqs } | Requirement::ExplicitAnd { and: reqs } | Requirement::Not { not: reqs } | Requirement::And(reqs) => {
+                reqs.iter().for_each(|r| self.check_requirement(r, region, location));
+            },
+            Requirement::Req(name) if !self.names.contains(name.as_str()) => {
+                self.report(region, location, format!("{} is not an item, helper, tech, event or flag", name));
+            },
+            Requirement::PreviousNode { previousNode: id } => self.check_node_id(region, location, "previousNode", *id),
+            Requirement::AdjacentRunway { adjacentRunway: a } => self.check_node_id(region, location, "adjacentRunway", a.fromNode),
+            Requirement::CanComeInCharged { canComeInCharged: c } => self.check_node_id(region, location, "canComeInCharged", c.fromNode),
+            Requirement::EnemyDamage { enemyDamage: e } if !self.world.enemies.iter().any(|we| we.name == e.enemy) => {
+                self.report(region, location, format!("enemyDamage refers to unknown enemy {}", e.enemy));
+            },
+            Requirement::Ammo { ammo: a } if AmmoType::from_name(&a.r#type).is_none() => {
+                self.report(region, location, format!("ammo refers to unknown ammo type {}", a.r#type));
+            },
+            Requirement::AmmoDrain { ammoDrain: a } if AmmoType::from_name(&a.r#type).is_none() => {
+                self.report(region, location, format!("ammoDrain refers to unknown ammo type {}", a.r#type));
+            },
+            Requirement::EnemyKill { enemyKill: e } => {
+                for enemy in e.enemies.iter().flatten() {
+                    if !self.world.enemies.iter().any(|we| &we.name == enemy) {
+                        self.report(region, location, format!("enemyKill refers to unknown enemy {}", enemy));
+                    }
+                }
+                for weapon in e.explicitWeapons.iter().flatten().chain(e.excludedWeapons.iter().flatten()) {
+                    if !self.world.weapons.iter().any(|w| &w.name == weapon || w.categories.contains(weapon)) {
+                        self.report(region, location, format!("enemyKill refers to unknown weapon {}", weapon));
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate;
+    use crate::test_world;
+
+    /* A room whose links refer to things the test world doesn't have */
+    const BROKEN_ROOM: &str = r#"{"$schema": "", "rooms": [
+     {"id": 30, "name": "Broken Room", "area": "Crateria", "subarea": "Central",
+      "nodes": [
+       {"id": 1, "name": "Broken Room Door", "nodeType": "door", "nodeSubType": "blue"},
+       {"id": 2, "name": "Broken Room Junction", "nodeType": "junction", "nodeSubType": "junction"}
+      ],
+      "links": [
+       {"from": 1, "to": [{"id": 2, "strats": [
+        {"name": "Item", "notable": false, "requires": ["Morh"]},
+        {"name": "Flag", "notable": false, "requires": [{"or": ["f_DefeatedMotherBrian", "Morph"]}]},
+        {"name": "Tech", "notable": false, "requires": ["canWaljump"]},
+        {"name": "Weapon", "notable": false, "requires": [{"enemyKill": {"enemies": [["Zoomer"]], "excludedWeapons": ["Beam", "Laser"]}}]}
+       ]}]}
+      ]}
+    ]}"#;
+
+    #[test]
+    fn test_world_is_clean() {
+        assert_eq!(validate(&test_world::world()), Vec::new());
+    }
+
+    #[test]
+    fn reports_unknown_references() {
+        let world = test_world::world_with(&[("region/crateria/broken.json", BROKEN_ROOM)]);
+        let messages = validate(&world).iter().map(|d| d.to_string()).collect::<Vec<String>>();
+        assert_eq!(messages, vec![
+            "Broken Room: link 1 -> 2, strat \"Item\": Morh is not an item, helper, tech, event or flag",
+            "Broken Room: link 1 -> 2, strat \"Flag\": f_DefeatedMotherBrian is not an item, helper, tech, event or flag",
+            "Broken Room: link 1 -> 2, strat \"Tech\": canWaljump is not an item, helper, tech, event or flag",
+            "Broken Room: link 1 -> 2, strat \"Weapon\": enemyKill refers to unknown weapon Laser"
+        ]);
+    }
+}
@@ -0,0 +1,121 @@
+/* JavaScript bindings, built with the `wasm` feature.
+   Everything is loaded from strings and byte arrays handed over from JS, nothing here touches the file system.
+   Placements, spoilers and location lists are passed back as JSON strings. */
+use wasm_bindgen::prelude::*;
+use crate::world::World;
+use crate::region::Region;
+use crate::node::Node;
+use crate::location::Location;
+use crate::fill::{self, FillAlgorithm, Placement};
+use crate::settings::{PresetFile, Settings};
+use crate::spoiler::Spoiler;
+use crate::patch::PatchFormat;
+use crate::rom::Rom;
+use std::collections::BTreeMap;
+
+fn js_error(e: impl ToString) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+#[wasm_bindgen]
+pub struct WasmWorld {
+    world: World,
+    settings: Settings
+}
+
+impl WasmWorld {
+    fn find_start(&self, start: &str, seed: u64) -> Result<(&Region, &Node), JsValue> {
+        if start == "ship" {
+            crate::start::vanilla_start(&self.world).map_err(js_error)
+        } else if start == "random" {
+            crate::start::random_start(&self.world, &[], &self.settings.get_techs(), 1, seed).map_err(js_error)
+        } else {
+            self.world.find_node(start).ok_or_else(|| js_error(format!("Could not find start location {}", start)))
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl WasmWorld {
+    /* `files` is a JSON object mapping paths inside sm-json-data (like "region/crateria/west.json") to the file contents */
+    #[wasm_bindgen(constructor)]
+    pub fn new(name: &str, files: &str) -> Result<WasmWorld, JsValue> {
+        let files: BTreeMap<String, String> = serde_json::from_str(files).map_err(js_error)?;
+        let mut world = World::load_from_files(name, &files).map_err(js_error)?;
+        let settings = PresetFile::default_presets().get("normal").map_err(js_error)?;
+        settings.apply(&mut world);
+        Ok(WasmWorld { world, settings })
+    }
+
+    /* Switches to one of the built-in difficulty presets */
+    #[wasm_bindgen(js_name = setPreset)]
+    pub fn set_preset(&mut self, preset: &str) -> Result<(), JsValue> {
+        let settings = PresetFile::default_presets().get(preset).map_err(js_error)?;
+        settings.apply(&mut self.world);
+        self.settings = settings;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = setTechs)]
+    pub fn set_techs(&mut self, techs: Vec<String>) {
+        self.settings.techs = techs;
+    }
+
+    pub fn presets() -> Vec<String> {
+        PresetFile::default_presets().names().iter().map(|n| n.to_string()).collect()
+    }
+
+    /* Names of the item locations reachable from `start` with the given items */
+    pub fn reachable(&self, items: Vec<String>, start: &str) -> Result<Vec<String>, JsValue> {
+        let items = items.iter().map(|i| i.as_ref()).collect::<Vec<&str>>();
+        let (start_region, start_node) = self.find_start(start, 0)?;
+
+        let flags = self.world.get_flags(&items, &self.settings.get_techs());
+        let locations = Location::available(&flags, &self.world, start_region, start_node).unwrap_or_default();
+        Ok(locations.iter().map(|l| l.name.to_string()).collect())
+    }
+
+    /* Generates a seed and returns the placement as JSON, `algorithm` is "assumed" or "forward" */
+    pub fn generate(&self, start: &str, seed: u64, algorithm: &str) -> Result<String, JsValue> {
+        let algorithm = match algorithm {
+            "assumed" => FillAlgorithm::Assumed,
+            "forward" => FillAlgorithm::Forward,
+            _ => return Err(js_error(format!("Unknown fill algorithm {}", algorithm)))
+        };
+        let (start_region, start_node) = self.find_start(start, seed)?;
+
+        let pool = self.world.get_item_pool();
+        let mut placement = fill::fill(&self.world, &pool, &self.settings.get_techs(), start_region, start_node, seed, algorithm).map_err(js_error)?;
+        placement.settings = Some(self.settings.clone());
+        placement.to_json().map_err(js_error)
+    }
+
+    /* The placement is rendered with the settings and doors stored in it, the current ones are put back afterwards */
+    pub fn spoiler(&mut self, placement: &str) -> Result<String, JsValue> {
+        let placement = Placement::from_json(placement).map_err(js_error)?;
+        let connections = self.world.connections.clone();
+        placement.apply(&mut self.world);
+        let spoiler = Spoiler::new(&self.world, &placement).and_then(|s| Ok(s.to_json()?));
+
+        self.settings.apply(&mut self.world);
+        if self.world.connections != connections {
+            self.world.set_connections(connections);
+        }
+        spoiler.map_err(js_error)
+    }
+
+    /* Patch for the given vanilla ROM, `format` is "ips" or "bps" */
+    #[wasm_bindgen(js_name = createPatch)]
+    pub fn create_patch(&self, placement: &str, rom: &[u8], format: &str) -> Result<Vec<u8>, JsValue> {
+        let format = match format {
+            "ips" => PatchFormat::Ips,
+            "bps" => PatchFormat::Bps,
+            _ => return Err(js_error(format!("Unknown patch format {}", format)))
+        };
+        let placement = Placement::from_json(placement).map_err(js_error)?;
+        let original = Rom::from_bytes(rom.to_vec()).map_err(js_error)?;
+        let mut patched = original.clone();
+        patched.apply_placement(&self.world, &placement).map_err(js_error)?;
+        patched.create_patch(&original, format).map_err(js_error)
+    }
+}
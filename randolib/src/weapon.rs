@@ -1,4 +1,5 @@
 use serde_derive::{Serialize,Deserialize};
+use crate::load_error::{LoadError, parse_json};
 use std::fs;
 use crate::requirement::Requirement;
 
@@ -6,8 +7,12 @@ use crate::requirement::Requirement;
 pub struct Weapon {
     pub id: i64,
     pub name: String,
+    pub damage: i64,
     pub situational: bool,
+    #[serde(default)]
+    pub hitsGroup: bool,
     pub useRequires: Requirement,
+    pub shotRequires: Option<Requirement>,
     pub categories: Vec<String>
 }
 
@@ -19,13 +24,17 @@ pub struct WeaponFile {
 }
 
 impl WeaponFile {
-    pub fn read(path: &str) -> Result<Vec<Weapon>, Box<dyn std::error::Error>> {
-        let data = fs::read_to_string(path)?;
-        let weapon_file: WeaponFile = serde_json::from_str(&data)?;
+    pub fn read(path: &str) -> Result<Vec<Weapon>, LoadError> {
+        let data = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+        WeaponFile::parse(&data, path)
+    }
+
+    pub fn parse(data: &str, path: &str) -> Result<Vec<Weapon>, LoadError> {
+        let weapon_file: WeaponFile = parse_json(data, path)?;
         if let Some(weapons) = weapon_file.weapons {
             Ok(weapons)
         } else {
-            bail!(format!("Could not find weapons node in weapon file: {:?}", path))
+            Err(LoadError::missing_section(path, "weapons"))
         }
     }
 }
\ No newline at end of file
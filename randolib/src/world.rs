@@ -5,9 +5,20 @@ use crate::enemy::{Enemy, EnemyFile};
 use crate::weapon::{Weapon, WeaponFile};
 use crate::helper::{Helper, HelperFile};
 use crate::tech::TechFile;
-use std::iter::FromIterator;
-use walkdir::WalkDir;
-use std::collections::HashSet;
+use crate::graph::Graph;
+use crate::compiled::{Compiled, Flags};
+use crate::inventory::Inventory;
+use crate::logic::Logic;
+use crate::goal::Goal;
+use crate::fill::Placement;
+use crate::playthrough::Playthrough;
+use crate::node::Node;
+use crate::requirement::Requirement;
+use crate::resources::Resources;
+use crate::location::State;
+use crate::source::{WorldSource, DirectorySource};
+use crate::load_error::LoadError;
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct World {
@@ -17,16 +28,62 @@ pub struct World {
     pub enemies: Vec<Enemy>,
     pub weapons: Vec<Weapon>,
     pub helpers: Vec<Helper>,
-    pub techs: Vec<Helper>
+    pub techs: Vec<Helper>,
+    #[serde(default)]
+    pub logic: Logic,
+    #[serde(default)]
+    pub goal: Goal,
+    #[serde(skip)]
+    pub graph: Graph,
+    #[serde(skip)]
+    pub compiled: Compiled
 }
 
 impl World {
-    pub fn get_flags(&self, items: &[&str], techs: &[&str]) -> HashSet<String> {
-        let hs_items = HashSet::from_iter(items.iter().map(|&s| s.to_owned()));
-        let hs_techs = HashSet::from_iter(techs.iter().map(|&s| s.to_owned()));
-        let active_helpers = self.resolve_helpers(&hs_items);
-        let active_techs = self.resolve_techs(&hs_items, &active_helpers, &hs_techs);
-        hs_items.into_iter().chain(active_helpers.into_iter()).chain(active_techs.into_iter()).collect()
+    /* The items, where tanks and expansions can appear more than once, along with the helpers they enable
+       and the techs from the list that are possible with them */
+    pub fn get_flags(&self, items: &[&str], techs: &[&str]) -> Inventory {
+        let mut inventory = self.inventory(items);
+        let mut state = State::with_capacity(0, 0, Resources::from_inventory(&inventory, &self.compiled.names));
+        self.resolve_helpers(&mut inventory, &mut state);
+        for id in self.resolve_techs(&inventory, &mut state).iter().filter(|&id| techs.contains(&self.compiled.names.name(id))) {
+            inventory.insert(id);
+        }
+        inventory
+    }
+
+    pub fn inventory(&self, items: &[&str]) -> Inventory {
+        Inventory::from_items(items, &self.compiled.names)
+    }
+
+    /* Rebuilds the compiled graph and requirements, needed after deserializing a world or changing its regions or connections */
+    pub fn build_graph(&mut self) {
+        self.graph = Graph::build(&self.regions, &self.connections);
+        self.compiled = Compiled::build(self);
+    }
+
+    /* Swaps in a different set of door connections, like the ones from the door randomizer */
+    pub fn set_connections(&mut self, connections: Vec<Connection>) {
+        self.connections = connections;
+        self.build_graph();
+    }
+
+    pub fn node_at(&self, index: usize) -> (&Region, &Node) {
+        let graph_node = &self.graph.nodes[index];
+        let region = &self.regions[graph_node.region];
+        (region, &region.nodes[graph_node.node])
+    }
+
+    pub fn get_region(&self, region_id: i64) -> Option<&Region> {
+        self.graph.regions.get(&region_id).map(|&r| &self.regions[r])
+    }
+
+    pub fn get_node(&self, region_id: i64, node_id: i64) -> Option<(&Region, &Node)> {
+        self.graph.index(region_id, node_id).map(|i| self.node_at(i))
+    }
+
+    pub fn find_node(&self, name: &str) -> Option<(&Region, &Node)> {
+        self.graph.node_names.get(name).map(|&i| self.node_at(i))
     }
 
     pub fn get_item_locations(&self) -> Option<Vec<&str>> {
@@ -38,94 +95,189 @@ impl World {
         }
     }
 
-    fn resolve_helpers(&self, items: &HashSet<String>) -> HashSet<String> {
-        let mut hs: HashSet<String> = HashSet::new();
-        let state = crate::location::State::new(0, 0);
-        let mut lastCount = 1;
+    /* The vanilla item pool, one entry per item node */
+    pub fn get_item_pool(&self) -> Vec<&str> {
+        self.regions.iter().flat_map(|r| &r.nodes).filter(|n| n.nodeType == Some(crate::node::NodeType::Item)).filter_map(|n| n.nodeItem.as_deref()).collect()
+    }
+
+    /* Plays through the placement from the given start and checks that the goal can be reached */
+    pub fn is_beatable(&self, placement: &Placement, start_region: &Region, start_node: &Node) -> bool {
+        Playthrough::from_start(self, placement, start_region, start_node).is_beatable
+    }
+
+    /* Every requirement in the world data, including helpers, techs and weapons */
+    pub fn get_requirements(&self) -> Vec<&Requirement> {
+        let mut reqs: Vec<&Requirement> = Vec::new();
+
+        for region in &self.regions {
+            for node in &region.nodes {
+                reqs.extend(node.interactionRequires.iter());
+                reqs.extend(node.view.iter());
+                for lock in node.locks.iter().flatten() {
+                    reqs.extend(lock.lock.iter());
+                    lock.unlockStrats.iter().flatten().chain(lock.bypassStrats.iter().flatten()).for_each(|s| s.collect_requirements(&mut reqs));
+                }
+                let runways = node.runways.iter().flatten().chain(node.sparking.iter().flat_map(|s| s.runways.iter().flatten()));
+                runways.flat_map(|r| r.strats.iter().flatten()).for_each(|s| s.collect_requirements(&mut reqs));
+                node.sparking.iter().flat_map(|s| s.canLeaveCharged.iter().flatten()).flat_map(|c| c.strats.iter().flatten()).for_each(|s| s.collect_requirements(&mut reqs));
+            }
+            for link in region.links.iter().flat_map(|l| &l.to) {
+                reqs.extend(link.requires.iter());
+                reqs.extend(link.unlock.iter());
+                link.strats.iter().flatten().for_each(|s| s.collect_requirements(&mut reqs));
+            }
+            for obstacle in region.obstacles.iter().flatten() {
+                reqs.extend(obstacle.requires.iter());
+                reqs.extend(obstacle.bypass.iter());
+            }
+        }
+
+        reqs.extend(self.helpers.iter().chain(self.techs.iter()).filter_map(|h| h.requires.as_ref()));
+        reqs.extend(self.weapons.iter().map(|w| &w.useRequires));
+        reqs
+    }
+
+    /* Every notable strat as (room name, strat name), the ones that can be toggled in the logic settings */
+    pub fn get_notable_strats(&self) -> Vec<(&str, &str)> {
+        let mut strats = Vec::new();
+        for region in &self.regions {
+            let mut room_strats = Vec::new();
+            for node in &region.nodes {
+                for lock in node.locks.iter().flatten() {
+                    room_strats.extend(lock.unlockStrats.iter().flatten().chain(lock.bypassStrats.iter().flatten()));
+                }
+                room_strats.extend(node.get_runways().flat_map(|r| r.strats.iter().flatten()));
+                room_strats.extend(node.sparking.iter().flat_map(|s| s.canLeaveCharged.iter().flatten()).flat_map(|c| c.strats.iter().flatten()));
+            }
+            room_strats.extend(region.links.iter().flat_map(|l| &l.to).flat_map(|l| l.strats.iter().flatten()));
+
+            for strat in room_strats.into_iter().filter(|s| s.notable) {
+                if !strats.contains(&(region.name.as_ref(), strat.name.as_ref())) {
+                    strats.push((region.name.as_ref(), strat.name.as_ref()));
+                }
+            }
+        }
+        strats
+    }
 
-        while lastCount != hs.len() {
-            lastCount = hs.len();
+    /* Names of everything the logic ever asks for, items in this set can open up new locations */
+    pub fn get_progression_names(&self) -> HashSet<&str> {
+        let mut names = HashSet::new();
+        for req in self.get_requirements() {
+            req.names(&mut names);
+        }
+        names
+    }
 
-            for helper in &self.helpers {
-                if let Some(hr) = &helper.requires {
-                    let checkItems: HashSet<String> = items.union(&hs).map(|i| i.to_owned()).collect();
-                    if hr.check(&checkItems, self, &state) && !hs.contains(&helper.name) {
-                        hs.insert(helper.name.to_owned());
-                    }
-                } else if !hs.contains(&helper.name) {
-                    hs.insert(helper.name.to_owned());
+    /* Helpers and techs become plain flags, so any resources they ask for are checked against a full state but never spent */
+    fn resolve_helpers(&self, inventory: &mut Inventory, state: &mut State) {
+        let mut last_count = usize::MAX;
+        while last_count != inventory.len() {
+            last_count = inventory.len();
+            for (id, requires) in &self.compiled.helpers {
+                state.resources = state.capacity;
+                if !inventory.contains(*id) && requires.as_ref().is_none_or(|r| r.check(inventory, self, state)) {
+                    inventory.insert(*id);
                 }
             }
         }
-        hs
-    }
-
-    fn resolve_techs(&self, items: &HashSet<String>, helpers: &HashSet<String>, techs: &HashSet<String>) -> HashSet<String> {
-        let mut hs: HashSet<String> = HashSet::new();
-        let state = crate::location::State::new(0, 0);
-        let mut lastCount = 1;
-
-        /* First resolve all techs possible with the items we have */
-        while lastCount != hs.len() {
-            lastCount = hs.len();
-
-            for helper in &self.techs {
-                if let Some(hr) = &helper.requires {
-                    let checkItems: HashSet<String> = items.union(&hs).cloned().collect::<HashSet<String>>().union(&helpers).cloned().collect();
-                    if hr.check(&checkItems, self, &state) && !hs.contains(&helper.name) {
-                        hs.insert(helper.name.to_owned());
-                    }
-                } else if !hs.contains(&helper.name) {
-                    hs.insert(helper.name.to_owned());
+    }
+
+    /* Every tech the items and helpers allow for, whether the player knows it or not. Techs can build on each other,
+       so this keeps going until nothing new turns up and leaves picking the known ones to the caller. */
+    fn resolve_techs(&self, inventory: &Inventory, state: &mut State) -> Flags {
+        let mut all = inventory.clone();
+        let mut techs = Flags::new();
+        let mut last_count = usize::MAX;
+        while last_count != techs.len() {
+            last_count = techs.len();
+            for (id, requires) in &self.compiled.techs {
+                state.resources = state.capacity;
+                if !techs.contains(*id) && requires.as_ref().is_none_or(|r| r.check(&all, self, state)) {
+                    techs.insert(*id);
+                    all.insert(*id);
                 }
             }
         }
+        techs
+    }
 
-        /* Then match the input techs with the list of resolved techs and return the matching entries */
-        hs.iter().filter(|&h| techs.contains(h)).cloned().collect()
+    pub fn load_from(name: &str, path: &str) -> Result<World, LoadError> {
+        World::load(name, &DirectorySource::new(path))
     }
 
-    pub fn load_from(name: &str, path: &str) -> Result<World, Box<dyn std::error::Error>> {
-        let region_files = WalkDir::new(format!("{}/region/", path)).into_iter().filter_map(|e| e.ok()).filter(|f| f.file_name().to_string_lossy().ends_with(".json"));
-        let connection_files = WalkDir::new(format!("{}/connection/", path)).into_iter().filter_map(|e| e.ok()).filter(|f| f.file_name().to_string_lossy().ends_with(".json"));
+    /* Same as load_from but with the files already in memory, keyed by their path relative to the data root.
+       Doesn't touch the file system, so it also works in the browser. */
+    pub fn load_from_files(name: &str, files: &BTreeMap<String, String>) -> Result<World, LoadError> {
+        World::load(name, files)
+    }
 
+    /* Builds a world from any source laid out like sm-json-data: rooms under region/, door connections under connection/,
+       and enemies/main.json, weapons/main.json, helpers.json and tech.json. Stops at the first broken file. */
+    pub fn load(name: &str, source: &dyn WorldSource) -> Result<World, LoadError> {
+        World::load_files(name, source, false).map_err(|mut errors| errors.remove(0))
+    }
+
+    /* Like load, but reads every file before giving up and returns all the errors found */
+    pub fn load_collecting(name: &str, source: &dyn WorldSource) -> Result<World, Vec<LoadError>> {
+        World::load_files(name, source, true)
+    }
+
+    fn load_files(name: &str, source: &dyn WorldSource, collect: bool) -> Result<World, Vec<LoadError>> {
+        let files = source.files().map_err(|e| vec![LoadError::io(".", e)])?;
+        let json_files = |dir: &'static str| files.iter().filter(move |p| p.starts_with(dir) && p.ends_with(".json"));
+        let read = |path: &str| source.read(path).map_err(|e| LoadError::io(path, e));
+
+        let mut errors = Vec::new();
         let mut regions: Vec<Region> = Vec::new();
         let mut connections: Vec<Connection> = Vec::new();
-        let mut enemies: Vec<Enemy> = Vec::new();
-        let mut weapons: Vec<Weapon> = Vec::new();
-        let mut helpers: Vec<Helper> = Vec::new();
-        let mut techs: Vec<Helper> = Vec::new();
-
-        for region_file in region_files {
-            let mut rs = RegionFile::read(&region_file.path().to_string_lossy().into_owned())?;
-            regions.append(&mut rs);
-        }
 
-        for connection_file in connection_files {
-            let mut cs = ConnectionFile::read(&connection_file.path().to_string_lossy().into_owned())?;
-            connections.append(&mut cs);
+        for path in json_files("region/") {
+            regions.append(&mut keep_going(read(path).and_then(|d| RegionFile::parse(&d, path)), &mut errors, collect)?);
         }
 
-        let mut es = EnemyFile::read(&format!("{}/enemies/main.json", path))?;
-        enemies.append(&mut es);
+        for path in json_files("connection/") {
+            connections.append(&mut keep_going(read(path).and_then(|d| ConnectionFile::parse(&d, path)), &mut errors, collect)?);
+        }
 
-        let mut ws = WeaponFile::read(&format!("{}/weapons/main.json", path))?;
-        weapons.append(&mut ws);
+        let enemies = keep_going(read("enemies/main.json").and_then(|d| EnemyFile::parse(&d, "enemies/main.json")), &mut errors, collect)?;
+        let weapons = keep_going(read("weapons/main.json").and_then(|d| WeaponFile::parse(&d, "weapons/main.json")), &mut errors, collect)?;
+        let helpers = keep_going(read("helpers.json").and_then(|d| HelperFile::parse(&d, "helpers.json")), &mut errors, collect)?;
+        let techs = keep_going(read("tech.json").and_then(|d| TechFile::parse(&d, "tech.json")), &mut errors, collect)?;
 
-        let mut hs = HelperFile::read(&format!("{}/helpers.json", path))?;
-        helpers.append(&mut hs);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
 
-        let mut ts = TechFile::read(&format!("{}/tech.json", path))?;
-        techs.append(&mut ts);
+        Ok(World::new(name, regions, connections, enemies, weapons, helpers, techs))
+    }
 
-        Ok(World {
+    pub fn new(name: &str, regions: Vec<Region>, connections: Vec<Connection>, enemies: Vec<Enemy>, weapons: Vec<Weapon>, helpers: Vec<Helper>, techs: Vec<Helper>) -> World {
+        let mut world = World {
             name: name.to_string(),
             regions,
             connections,
             enemies,
             weapons,
             helpers,
-            techs
-        })
+            techs,
+            logic: Logic::default(),
+            goal: Goal::default(),
+            graph: Graph::default(),
+            compiled: Compiled::default()
+        };
+        world.build_graph();
+        world
+    }
+}
+
+/* Records a failed file and either moves on to the next one or stops the load */
+fn keep_going<T>(result: Result<Vec<T>, LoadError>, errors: &mut Vec<LoadError>, collect: bool) -> Result<Vec<T>, Vec<LoadError>> {
+    match result {
+        Ok(values) => Ok(values),
+        Err(e) => {
+            errors.push(e);
+            if collect { Ok(Vec::new()) } else { Err(std::mem::take(errors)) }
+        }
     }
 }